@@ -16,22 +16,40 @@ pub enum Base64SubCommand {
 pub struct Base64EncodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
+    /// standard, standard-nopad, urlsafe, urlsafe-pad, mime, crypt or bcrypt
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
-    pub format: Base64Format
+    pub format: Base64Format,
+    /// Read the input byte for byte, without decompressing gzip/zstd/bzip2
+    #[arg(long)]
+    pub raw: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
+    /// standard, standard-nopad, urlsafe, urlsafe-pad, mime, crypt, bcrypt or auto
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
-    pub format: Base64Format
+    pub format: Base64Format,
+    /// Write the decoded bytes to this file, "-" for stdout (raw bytes unless it is a terminal)
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
 }
 
 #[derive(Debug, Clone,Copy)]
 pub enum Base64Format {
     Standard,
-    UrlSafe 
+    StandardNoPad,
+    UrlSafe,
+    UrlSafePad,
+    /// standard alphabet wrapped at 76 columns with CRLF (RFC 2045)
+    Mime,
+    /// `./0-9A-Za-z` as used by crypt(3), unpadded
+    Crypt,
+    /// `./A-Za-z0-9` as used by bcrypt, unpadded
+    Bcrypt,
+    /// decode only: detect the alphabet and padding, ignoring whitespace anywhere
+    Auto,
 }
 
 fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
@@ -44,7 +62,13 @@ impl FromStr for Base64Format {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "standard" => Ok(Base64Format::Standard),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "urlsafe-pad" => Ok(Base64Format::UrlSafePad),
+            "mime" => Ok(Base64Format::Mime),
+            "crypt" => Ok(Base64Format::Crypt),
+            "bcrypt" => Ok(Base64Format::Bcrypt),
+            "auto" => Ok(Base64Format::Auto),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
@@ -54,7 +78,13 @@ impl From<Base64Format> for &'static str {
     fn from(format: Base64Format) -> Self {
         match format {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafePad => "urlsafe-pad",
+            Base64Format::Mime => "mime",
+            Base64Format::Crypt => "crypt",
+            Base64Format::Bcrypt => "bcrypt",
+            Base64Format::Auto => "auto",
         }
     }
 }
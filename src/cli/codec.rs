@@ -0,0 +1,86 @@
+use std::{fmt, str::FromStr};
+use clap::Parser;
+
+use super::verify_file;
+
+#[derive(Debug, Parser)]
+pub struct EncodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    /// base64, base64url, hex, base32, base32hex, base58, base58check, base85 (Ascii85) or z85
+    #[arg(long, value_parser = parse_codec)]
+    pub codec: Codec,
+}
+
+#[derive(Debug, Parser)]
+pub struct DecodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    /// base64, base64url, hex, base32, base32hex, base58, base58check, base85 (Ascii85) or z85
+    #[arg(long, value_parser = parse_codec)]
+    pub codec: Codec,
+    /// Write the decoded bytes to this file, "-" for stdout (raw bytes unless it is a terminal)
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Codec {
+    Base64,
+    Base64Url,
+    Hex,
+    Base32,
+    Base32Hex,
+    /// Bitcoin alphabet
+    Base58,
+    /// Base58 with a 4-byte double SHA-256 checksum
+    Base58Check,
+    /// Ascii85 without the `<~ ~>` delimiters
+    Base85,
+    Z85,
+}
+
+fn parse_codec(codec: &str) -> Result<Codec, anyhow::Error> {
+    codec.parse()
+}
+
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64" => Ok(Codec::Base64),
+            "base64url" => Ok(Codec::Base64Url),
+            "hex" => Ok(Codec::Hex),
+            "base32" => Ok(Codec::Base32),
+            "base32hex" => Ok(Codec::Base32Hex),
+            "base58" => Ok(Codec::Base58),
+            "base58check" => Ok(Codec::Base58Check),
+            "base85" => Ok(Codec::Base85),
+            "z85" => Ok(Codec::Z85),
+            _ => Err(anyhow::anyhow!("Invalid codec")),
+        }
+    }
+}
+
+impl From<Codec> for &'static str {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Base64 => "base64",
+            Codec::Base64Url => "base64url",
+            Codec::Hex => "hex",
+            Codec::Base32 => "base32",
+            Codec::Base32Hex => "base32hex",
+            Codec::Base58 => "base58",
+            Codec::Base58Check => "base58check",
+            Codec::Base85 => "base85",
+            Codec::Z85 => "z85",
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -1,27 +1,61 @@
-use clap::Parser;
-use std::{fmt,str::FromStr};
-use super::verify_file;
+use clap::{Args, Parser};
+use std::{fmt, path::PathBuf, str::FromStr};
+use super::{verify_file, verify_glob};
 
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Json,
     Yaml,
+    Csv,
+    Xml,
 }
 
-/// 处理 CSV 的选项
+/// XML 输出的选项
+#[derive(Debug, Clone, Args)]
+pub struct XmlOpts {
+    /// XML 根元素名称
+    #[arg(long, default_value = "records")]
+    pub xml_root: String,
+
+    /// XML 中每一行记录的元素名称
+    #[arg(long, default_value = "record")]
+    pub xml_row: String,
+
+    /// 将字段输出为行元素的属性，而不是子元素
+    #[arg(long)]
+    pub xml_attributes: bool,
+}
+
+/// 处理 CSV 的选项，不带子命令时将 CSV 转换为其他格式
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CsvOpts {
-    /// 输入文件路径，短选项 -i，长选项 --input，使用 verify_input_file 函数验证文件存在
-    #[arg(short, long, value_parser = verify_file)]
-    pub input: String,
+    /// 对 CSV 数据进行重塑的子命令
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCommand>,
+
+    /// 输入文件路径或 glob 模式（如 'exports/*.csv'），短选项 -i，长选项 --input，可重复指定
+    #[arg(short, long, value_parser = verify_glob, required = true)]
+    pub input: Vec<String>,
 
     /// 输出文件路径，短选项 -o，长选项 --output，默认值为 "output.json"
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with = "out_dir")]
     pub output: Option<String>,
 
+    /// 批量转换时的输出目录，每个输入文件输出为 <out-dir>/<文件名>.<format>
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// 批量转换时并发的线程数，默认为 CPU 核数
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
     #[arg( long, value_parser = parse_format, default_value = "json")]
     pub format: OutputFormat,
 
+    #[command(flatten)]
+    pub xml: XmlOpts,
+
     /// CSV 文件的分隔符，短选项 -d，长选项 --delimiter，默认值为 ','
     #[arg(short, long, default_value_t = ',')]
     pub delimiter: char,
@@ -29,19 +63,137 @@ pub struct CsvOpts {
     /// 是否包含 CSV 文件的表头，长选项 --header，默认值为 true
     #[arg(long, default_value_t = true)]
     pub header: bool,
+
+    /// 读取 .xlsx/.xls/.ods 时的工作表名称或索引（从 0 开始），默认第一个工作表
+    #[arg(long)]
+    pub sheet: Option<String>,
+
+    /// 读取表格文件时表头所在的行（从 0 开始），之前的行会被忽略
+    #[arg(long, default_value_t = 0)]
+    pub header_row: usize,
+
+    /// 日期单元格的输出格式（strftime），默认输出 ISO 8601
+    #[arg(long)]
+    pub date_format: Option<String>,
+
+    /// 需要遮盖的列名，长选项 --mask，可重复指定或用逗号分隔
+    #[arg(long, value_delimiter = ',')]
+    pub mask: Vec<String>,
+
+    /// 需要假名化的列名，长选项 --pseudonymize，使用截断的带密钥 BLAKE3 哈希替换原值
+    #[arg(long, value_delimiter = ',', requires = "key")]
+    pub pseudonymize: Vec<String>,
+
+    /// 假名化使用的 BLAKE3 密钥文件，短选项 -k，长选项 --key
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: Option<String>,
+}
+
+
+
+/// CSV 重塑子命令
+#[derive(Debug, Parser)]
+pub enum CsvSubCommand {
+    #[command(about = "透视表：将某一列的取值展开为新的列")]
+    Pivot(CsvPivotOpts),
+    #[command(about = "逆透视：将多列折叠为 变量/值 两列")]
+    Melt(CsvMeltOpts),
+}
+
+/// 透视表的选项
+#[derive(Debug, Parser)]
+pub struct CsvPivotOpts {
+    /// 输入文件路径，短选项 -i，长选项 --input
+    #[arg(short, long, value_parser = verify_file)]
+    pub input: String,
+
+    /// 输出文件路径，短选项 -o，长选项 --output，默认值为 "output.<format>"
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    #[arg(long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub xml: XmlOpts,
+
+    /// 作为行索引的列
+    #[arg(long)]
+    pub index: String,
+
+    /// 取值展开为新列的列
+    #[arg(long)]
+    pub columns: String,
+
+    /// 需要聚合的列，聚合方式为 count 时可以省略
+    #[arg(long)]
+    pub values: Option<String>,
+
+    /// 聚合方式：count、sum、mean、min、max、first
+    #[arg(long, value_parser = parse_aggregate, default_value = "count")]
+    pub agg: Aggregate,
 }
 
+/// 逆透视的选项
+#[derive(Debug, Parser)]
+pub struct CsvMeltOpts {
+    /// 输入文件路径，短选项 -i，长选项 --input
+    #[arg(short, long, value_parser = verify_file)]
+    pub input: String,
+
+    /// 输出文件路径，短选项 -o，长选项 --output，默认值为 "output.<format>"
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    #[arg(long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub xml: XmlOpts,
 
+    /// 保持不变的标识列，可重复指定或用逗号分隔
+    #[arg(long, value_delimiter = ',', required = true)]
+    pub id: Vec<String>,
 
-fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
+    /// 需要折叠的列，默认为除标识列以外的所有列
+    #[arg(long, value_delimiter = ',')]
+    pub vars: Vec<String>,
+
+    /// 输出中保存原列名的列名
+    #[arg(long, default_value = "variable")]
+    pub var_name: String,
+
+    /// 输出中保存原值的列名
+    #[arg(long, default_value = "value")]
+    pub value_name: String,
+}
+
+/// 透视表的聚合方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Mean,
+    Min,
+    Max,
+    First,
+}
+
+pub(crate) fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
     format.parse()
 }
 
+fn parse_aggregate(agg: &str) -> Result<Aggregate, anyhow::Error> {
+    agg.parse()
+}
+
 impl From<OutputFormat> for &'static str {
     fn from(format: OutputFormat) -> Self {
         match format{
             OutputFormat::Json => "json",
             OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Xml => "xml",
         }
     }
 }
@@ -53,6 +205,8 @@ impl FromStr for OutputFormat {
         match s{
             "json" => Ok(OutputFormat::Json),
             "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            "xml" => Ok(OutputFormat::Xml),
             v =>anyhow::bail!("Unsupported format: {}", v),
         }
     }
@@ -62,4 +216,49 @@ impl fmt::Display for OutputFormat {
     fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", Into::<&str>::into(*self))
     }
-}
\ No newline at end of file
+}
+
+impl From<Aggregate> for &'static str {
+    fn from(agg: Aggregate) -> Self {
+        match agg {
+            Aggregate::Count => "count",
+            Aggregate::Sum => "sum",
+            Aggregate::Mean => "mean",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::First => "first",
+        }
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "count" => Ok(Aggregate::Count),
+            "sum" => Ok(Aggregate::Sum),
+            "mean" => Ok(Aggregate::Mean),
+            "min" => Ok(Aggregate::Min),
+            "max" => Ok(Aggregate::Max),
+            "first" => Ok(Aggregate::First),
+            v => anyhow::bail!("Unsupported aggregate: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl Default for XmlOpts {
+    fn default() -> Self {
+        Self {
+            xml_root: "records".into(),
+            xml_row: "record".into(),
+            xml_attributes: false,
+        }
+    }
+}
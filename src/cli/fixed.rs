@@ -0,0 +1,24 @@
+use clap::Parser;
+use super::{csv::parse_format, verify_file, OutputFormat, XmlOpts};
+
+/// 处理定长文本文件的选项
+#[derive(Debug, Parser)]
+pub struct FixedOpts {
+    /// 输入文件路径，"-" 表示标准输入
+    #[arg(value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    /// 描述字段名称、偏移、宽度和类型的 YAML 布局文件，长选项 --layout
+    #[arg(long, value_parser = verify_file)]
+    pub layout: String,
+
+    /// 输出文件路径，短选项 -o，长选项 --output，默认值为 "output.<format>"
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    #[arg(long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub xml: XmlOpts,
+}
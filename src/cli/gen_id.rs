@@ -0,0 +1,77 @@
+use std::{fmt, str::FromStr};
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+pub enum GenSubCommand {
+    #[command(about = "Generate UUIDs, ULIDs or nanoids")]
+    Id(GenIdOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenIdOpts {
+    #[arg(long, default_value = "uuid4", value_parser = parse_kind)]
+    pub kind: IdKind,
+
+    /// Number of ids to generate
+    #[arg(short, long, default_value_t = 1)]
+    pub count: usize,
+
+    /// Unix time in milliseconds for uuid7 and ulid instead of the current time
+    #[arg(long)]
+    pub timestamp: Option<u64>,
+
+    /// Alphabet for nanoid
+    #[arg(long, default_value = "_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ")]
+    pub alphabet: String,
+
+    /// Length of a nanoid
+    #[arg(short, long, default_value_t = 21)]
+    pub length: usize,
+
+    /// UNSAFE FOR PRODUCTION: seed the generator so the output is reproducible, for test fixtures only
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdKind {
+    Uuid4,
+    Uuid7,
+    Ulid,
+    Nanoid,
+}
+
+fn parse_kind(kind: &str) -> Result<IdKind, anyhow::Error> {
+    kind.parse()
+}
+
+impl FromStr for IdKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uuid4" => Ok(IdKind::Uuid4),
+            "uuid7" => Ok(IdKind::Uuid7),
+            "ulid" => Ok(IdKind::Ulid),
+            "nanoid" => Ok(IdKind::Nanoid),
+            _ => Err(anyhow::anyhow!("Invalid id kind")),
+        }
+    }
+}
+
+impl From<IdKind> for &'static str {
+    fn from(kind: IdKind) -> Self {
+        match kind {
+            IdKind::Uuid4 => "uuid4",
+            IdKind::Uuid7 => "uuid7",
+            IdKind::Ulid => "ulid",
+            IdKind::Nanoid => "nanoid",
+        }
+    }
+}
+
+impl fmt::Display for IdKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -1,19 +1,259 @@
-use clap::Parser;
+use std::{fmt, str::FromStr};
+use clap::{ArgAction, Args, Parser};
 
+use super::{csv::parse_format, verify_file, OutputFormat};
+
+/// Generate passwords, audit existing ones with `check` or derive site passwords with `derive`
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct GenPassOpts {
-    #[arg(short, long, default_value_t = 16)]
-    pub length: u8,
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
+
+    #[command(flatten)]
+    pub policy: PolicyOpts,
+
+    /// char draws from the character classes, pronounceable alternates consonants and vowels
+    #[arg(long, default_value = "char", value_parser = parse_mode)]
+    pub mode: GenPassMode,
+
+    /// Template where C/c is a consonant, V/v a vowel, 9 a digit, ! a symbol and \ escapes, e.g. "Cvccvc-999-Cvccvc"
+    #[arg(long, conflicts_with_all = ["mode", "length", "words"])]
+    pub pattern: Option<String>,
+
+    /// Generate a diceware passphrase with this many words instead of a character password
+    #[arg(long, conflicts_with_all = ["length", "policy", "mode"])]
+    pub words: Option<usize>,
+
+    /// Separator between passphrase words
+    #[arg(long, default_value = "-", requires = "words")]
+    pub separator: String,
+
+    /// Wordlist file with one word per line, defaults to the embedded EFF long wordlist
+    #[arg(long, value_parser = verify_file, requires = "words")]
+    pub wordlist: Option<String>,
+
+    /// Capitalize the first letter of every word
+    #[arg(long, requires = "words")]
+    pub capitalize: bool,
 
-    #[arg(long, default_value_t = true)]
+    /// Number of random digits appended to randomly chosen words
+    #[arg(long, default_value_t = 0, requires = "words")]
+    pub digits: usize,
+
+    #[command(flatten)]
+    pub strength: StrengthOpts,
+
+    /// UNSAFE FOR PRODUCTION: seed the generator so the output is reproducible, for test fixtures only
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Number of passwords to generate
+    #[arg(short, long, default_value_t = 1)]
+    pub count: usize,
+
+    /// Print the passwords with their score, crack time and entropy as json, yaml or csv
+    #[arg(long, value_parser = parse_format)]
+    pub output_format: Option<OutputFormat>,
+}
+
+/// Which characters a generated password may contain
+#[derive(Debug, Clone, Args)]
+pub struct PolicyOpts {
+    /// Password length [default: 16, or the policy file's length]
+    #[arg(short, long)]
+    pub length: Option<usize>,
+
+    #[arg(long, default_value_t = true, action = ArgAction::Set, conflicts_with = "policy")]
     pub uppercase: bool,
 
-    #[arg(long, default_value_t = true)]
+    #[arg(long, default_value_t = true, action = ArgAction::Set, conflicts_with = "policy")]
     pub lowercase: bool,
 
-    #[arg(long, default_value_t = true)]
+    #[arg(long, default_value_t = true, action = ArgAction::Set, conflicts_with = "policy")]
     pub number: bool,
 
-    #[arg(long, default_value_t = true)]
-    pub symbol: bool,  
-}
\ No newline at end of file
+    #[arg(long, default_value_t = true, action = ArgAction::Set, conflicts_with = "policy")]
+    pub symbol: bool,
+
+    /// Draw characters only from this set instead of the built-in classes
+    #[arg(long, conflicts_with_all = ["symbols", "include_ambiguous", "policy"])]
+    pub charset: Option<String>,
+
+    /// Replace the default symbol set, e.g. "!#$"
+    #[arg(long, conflicts_with = "policy")]
+    pub symbols: Option<String>,
+
+    /// Characters never used in the password
+    #[arg(long, default_value = "", conflicts_with = "policy")]
+    pub exclude: String,
+
+    /// Also use easily confused characters like 0, O and l
+    #[arg(long, conflicts_with = "policy")]
+    pub include_ambiguous: bool,
+
+    /// Minimum number of uppercase characters [default: 1]
+    #[arg(long, conflicts_with = "policy")]
+    pub min_upper: Option<usize>,
+
+    /// Minimum number of lowercase characters [default: 1]
+    #[arg(long, conflicts_with = "policy")]
+    pub min_lower: Option<usize>,
+
+    /// Minimum number of digits [default: 1]
+    #[arg(long, conflicts_with = "policy")]
+    pub min_number: Option<usize>,
+
+    /// Minimum number of symbols [default: 1]
+    #[arg(long, conflicts_with = "policy")]
+    pub min_symbol: Option<usize>,
+
+    /// YAML policy file with site-specific rules, --length still overrides its length
+    #[arg(long, value_parser = verify_file)]
+    pub policy: Option<String>,
+}
+
+/// Minimum strength a generated password must meet
+#[derive(Debug, Clone, Args)]
+pub struct StrengthOpts {
+    /// Regenerate until the zxcvbn score is at least this value (0-4)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub min_score: Option<u8>,
+
+    /// Minimum entropy in bits; fails if the requested length and charset can't reach it
+    #[arg(long)]
+    pub min_entropy: Option<f64>,
+
+    /// Sorted SHA-1 Have I Been Pwned dump; candidates found in it are regenerated
+    #[arg(long, value_parser = verify_file)]
+    pub breach_db: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub enum GenPassSubCommand {
+    #[command(about = "Check the strength of existing passwords, one per line")]
+    Check(GenPassCheckOpts),
+    #[command(about = "Derive a reproducible site password from a master secret")]
+    Derive(Box<GenPassDeriveOpts>),
+    #[command(name = "apikey", about = "Generate an API key with a prefix and checksum")]
+    ApiKey(ApiKeyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassCheckOpts {
+    /// File with one password per line, or "-" for stdin
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    /// Context words that make a password weaker, e.g. the account name and email
+    #[arg(long, value_delimiter = ',')]
+    pub user_input: Vec<String>,
+
+    /// Include the checked passwords in the output
+    #[arg(long)]
+    pub show_password: bool,
+
+    /// Sorted SHA-1 Have I Been Pwned dump, reports how often each password was breached
+    #[arg(long, value_parser = verify_file)]
+    pub breach_db: Option<String>,
+
+    #[arg(long, value_parser = parse_format, default_value = "yaml")]
+    pub output_format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassDeriveOpts {
+    /// Site the password is for, compared case-insensitively
+    #[arg(long)]
+    pub site: String,
+
+    /// Account name on the site
+    #[arg(long)]
+    pub login: String,
+
+    /// Bump to rotate the password without changing the master secret
+    #[arg(long, default_value_t = 1)]
+    pub counter: u32,
+
+    /// File holding the master secret, prompts without echo when omitted
+    #[arg(long, value_parser = verify_file)]
+    pub master_file: Option<String>,
+
+    #[command(flatten)]
+    pub policy: PolicyOpts,
+
+    #[command(flatten)]
+    pub strength: StrengthOpts,
+}
+
+/// Generate an API key, or check one with the `verify` subcommand
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ApiKeyOpts {
+    #[command(subcommand)]
+    pub cmd: Option<ApiKeySubCommand>,
+
+    /// Prefix identifying the key type, e.g. rcli_live
+    #[arg(long, default_value = "rcli")]
+    pub prefix: String,
+
+    /// Bytes of entropy in the random part
+    #[arg(long, default_value_t = 32)]
+    pub bytes: usize,
+
+    /// BLAKE3 key file, prints the keyed hash of the API key for server-side storage
+    #[arg(long, value_parser = verify_file)]
+    pub hash_key: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub enum ApiKeySubCommand {
+    #[command(about = "Check an API key's checksum offline")]
+    Verify(ApiKeyVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct ApiKeyVerifyOpts {
+    pub key: String,
+
+    /// BLAKE3 key file, also prints the keyed hash to compare with the stored one
+    #[arg(long, value_parser = verify_file)]
+    pub hash_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenPassMode {
+    Char,
+    Pronounceable,
+}
+
+fn parse_mode(mode: &str) -> Result<GenPassMode, anyhow::Error> {
+    mode.parse()
+}
+
+impl FromStr for GenPassMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "char" => Ok(GenPassMode::Char),
+            "pronounceable" => Ok(GenPassMode::Pronounceable),
+            _ => Err(anyhow::anyhow!("Invalid mode")),
+        }
+    }
+}
+
+impl From<GenPassMode> for &'static str {
+    fn from(mode: GenPassMode) -> Self {
+        match mode {
+            GenPassMode::Char => "char",
+            GenPassMode::Pronounceable => "pronounceable",
+        }
+    }
+}
+
+impl fmt::Display for GenPassMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
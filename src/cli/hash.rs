@@ -0,0 +1,97 @@
+use std::{fmt, str::FromStr};
+use clap::Parser;
+
+use super::verify_file;
+
+#[derive(Debug, Parser)]
+pub enum HashSubCommand {
+    #[command(about = "Hash a password into a PHC string (modular crypt for bcrypt)")]
+    Password(HashPasswordOpts),
+    #[command(about = "Verify a password against a PHC or bcrypt hash")]
+    Verify(HashVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct HashPasswordOpts {
+    #[arg(long, default_value = "argon2id", value_parser = parse_algorithm)]
+    pub algo: HashAlgorithm,
+
+    /// Read the password from this file or "-" for stdin, prompts twice without echo when omitted
+    #[arg(long, value_parser = verify_file)]
+    pub password_file: Option<String>,
+
+    /// Argon2id memory cost in KiB
+    #[arg(long, default_value_t = 19 * 1024)]
+    pub memory: u32,
+
+    /// Argon2id iterations
+    #[arg(long, default_value_t = 2)]
+    pub iterations: u32,
+
+    /// Argon2id and scrypt parallelism
+    #[arg(long, default_value_t = 1)]
+    pub parallelism: u32,
+
+    /// bcrypt cost, the work doubles with every step
+    #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u32).range(4..=31))]
+    pub cost: u32,
+
+    /// scrypt CPU/memory cost as log2(N)
+    #[arg(long, default_value_t = 17)]
+    pub log_n: u8,
+
+    /// scrypt block size r
+    #[arg(long, default_value_t = 8)]
+    pub block_size: u32,
+}
+
+#[derive(Debug, Parser)]
+pub struct HashVerifyOpts {
+    /// PHC string, or a $2b$ bcrypt hash
+    #[arg(long)]
+    pub hash: String,
+
+    /// Read the password from this file or "-" for stdin, prompts without echo when omitted
+    #[arg(long, value_parser = verify_file)]
+    pub password_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Argon2id,
+    Bcrypt,
+    Scrypt,
+}
+
+fn parse_algorithm(algorithm: &str) -> Result<HashAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "argon2id" => Ok(HashAlgorithm::Argon2id),
+            "bcrypt" => Ok(HashAlgorithm::Bcrypt),
+            "scrypt" => Ok(HashAlgorithm::Scrypt),
+            _ => Err(anyhow::anyhow!("Invalid algorithm")),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for &'static str {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Argon2id => "argon2id",
+            HashAlgorithm::Bcrypt => "bcrypt",
+            HashAlgorithm::Scrypt => "scrypt",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
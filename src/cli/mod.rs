@@ -1,19 +1,28 @@
 mod csv;
+mod fixed;
 mod genpass;
+mod gen_id;
 mod base64;
+mod codec;
 mod text;
 mod http;
+mod otp;
+mod hash;
 
 use clap::Parser;
 use std::path::PathBuf;
 
-pub use self::{csv::CsvOpts, genpass::GenPassOpts};
+pub use self::{csv::CsvOpts, fixed::FixedOpts, genpass::{ApiKeySubCommand, GenPassMode, GenPassOpts, GenPassSubCommand, PolicyOpts, StrengthOpts}};
 use std::path::Path;
 pub use self::{
     base64::{Base64Format, Base64SubCommand},
-    csv::OutputFormat,
+    codec::{Codec, DecodeOpts, EncodeOpts},
+    csv::{Aggregate, CsvMeltOpts, CsvPivotOpts, CsvSubCommand, OutputFormat, XmlOpts},
     text::{TextSignFormat,TextSubCommand},
     http::HttpSubCommand,
+    otp::{OtpAlgorithm, OtpParams, OtpSubCommand},
+    gen_id::{GenIdOpts, GenSubCommand, IdKind},
+    hash::{HashAlgorithm, HashPasswordOpts, HashSubCommand},
 };
 
 /// 解析命令行参数的结构体
@@ -31,17 +40,35 @@ pub enum SubCommand {
     /// 处理 CSV 文件的子命令
     #[command(name = "csv", about = "处理 CSV 文件或将 CSV 转换为其他格式")]
     Csv(CsvOpts),
+    /// 将定长文本文件转换为其他格式的子命令
+    #[command(name = "fixed", about = "将定长文本文件转换为 JSON/YAML/CSV")]
+    Fixed(FixedOpts),
     #[command(name = "genpass", about = "生成一个随机密码")]
     GenPass(GenPassOpts),
 
+    #[command(subcommand, about = "Generate identifiers for fixtures and test data")]
+    Gen(GenSubCommand),
+
     #[command(subcommand)]
     Base64(Base64SubCommand),
 
+    #[command(name = "encode", about = "Encode bytes as hex, base32, base58, base64, base85 or z85")]
+    Encode(EncodeOpts),
+
+    #[command(name = "decode", about = "Decode hex, base32, base58, base64, base85 or z85 back to bytes")]
+    Decode(DecodeOpts),
+
     #[command(subcommand)]
     Text(TextSubCommand),
 
     #[command(subcommand)]
     Http(HttpSubCommand),
+
+    #[command(subcommand, about = "Generate and verify HOTP/TOTP one-time passwords")]
+    Otp(OtpSubCommand),
+
+    #[command(subcommand, about = "Hash and verify passwords with Argon2id, bcrypt or scrypt")]
+    Hash(HashSubCommand),
 }
 
 /// 验证输入文件是否存在的函数
@@ -49,9 +76,22 @@ fn verify_file(file_name: &str) -> Result<String, &'static str> {
     if file_name == "-" || Path::new(file_name).exists() {
         Ok(file_name.into())
     } else {
-        Err("文件不存在!".into())
+        Err("文件不存在!")
     } 
 }
+/// 验证输入是已存在的文件，或至少匹配一个文件的 glob 模式
+fn verify_glob(pattern: &str) -> Result<String, &'static str> {
+    if verify_file(pattern).is_ok() {
+        return Ok(pattern.into());
+    }
+    let matched = glob::glob(pattern).is_ok_and(|mut paths| paths.any(|p| p.is_ok_and(|p| p.is_file())));
+    if matched {
+        Ok(pattern.into())
+    } else {
+        Err("没有匹配的文件!")
+    }
+}
+
 fn verify_path(path: &str) -> Result<PathBuf, &'static str> {
     let p = Path::new(path);
     if p.exists() && p.is_dir() {
@@ -72,6 +112,13 @@ mod tests {
         assert_eq!(verify_file("Cargo.toml"), Ok("Cargo.toml".into()));
         assert_eq!(verify_file("no-exist"), Err("文件不存在!"));
     }
+
+    #[test]
+    fn test_verify_glob() {
+        assert_eq!(verify_glob("Cargo.toml"), Ok("Cargo.toml".into()));
+        assert_eq!(verify_glob("fixtures/*.txt"), Ok("fixtures/*.txt".into()));
+        assert_eq!(verify_glob("fixtures/*.none"), Err("没有匹配的文件!"));
+    }
 }
 
 
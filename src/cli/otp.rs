@@ -0,0 +1,129 @@
+use std::{fmt, str::FromStr};
+use clap::{Args, Parser};
+
+use super::verify_file;
+
+#[derive(Debug, Parser)]
+pub enum OtpSubCommand {
+    #[command(about = "Generate a one-time password from a base32 secret")]
+    Generate(OtpGenerateOpts),
+    #[command(about = "Verify a one-time password")]
+    Verify(OtpVerifyOpts),
+    #[command(about = "Create a new secret and print its otpauth:// URI")]
+    New(OtpNewOpts),
+}
+
+/// Parameters shared by every otp subcommand, they must match the authenticator's
+#[derive(Debug, Clone, Args)]
+pub struct OtpParams {
+    #[arg(long, default_value = "sha1", value_parser = parse_algorithm)]
+    pub algorithm: OtpAlgorithm,
+
+    /// Number of digits in a code
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=9))]
+    pub digits: u32,
+
+    /// TOTP time step in seconds
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub period: u64,
+
+    /// Use counter based HOTP (RFC 4226) instead of TOTP (RFC 6238)
+    #[arg(long)]
+    pub hotp: bool,
+
+    /// HOTP counter
+    #[arg(long, default_value_t = 0, requires = "hotp")]
+    pub counter: u64,
+
+    /// Unix time used for TOTP instead of the current time
+    #[arg(long, conflicts_with = "hotp")]
+    pub time: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpGenerateOpts {
+    /// File holding the base32 secret
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub secret_file: String,
+
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    pub code: String,
+
+    /// File holding the base32 secret
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub secret_file: String,
+
+    /// Number of time steps (TOTP) or later counters (HOTP) also accepted
+    #[arg(short, long, default_value_t = 1)]
+    pub window: u64,
+
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpNewOpts {
+    /// Service the account belongs to
+    #[arg(long)]
+    pub issuer: String,
+
+    /// Account name shown in the authenticator
+    #[arg(long)]
+    pub account: String,
+
+    /// Secret size in bytes [default: the algorithm's output size]
+    #[arg(long)]
+    pub bytes: Option<usize>,
+
+    /// Write the secret to this new file (owner-only permissions) instead of printing it
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn parse_algorithm(algorithm: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(anyhow::anyhow!("Invalid algorithm")),
+        }
+    }
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        match algorithm {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
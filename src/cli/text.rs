@@ -24,6 +24,10 @@ pub struct TextSignOpts {
 
     #[arg(long, default_value = "blake3", value_parser = parse_format)]
     pub format: TextSignFormat,
+
+    /// Read the input byte for byte, without decompressing gzip/zstd/bzip2
+    #[arg(long)]
+    pub raw: bool,
 }
 
 
@@ -39,7 +43,11 @@ pub struct TextVerifyOpts {
     pub format: TextSignFormat,
 
     #[arg(short, long)]
-    pub sig:String
+    pub sig:String,
+
+    /// Read the input byte for byte, without decompressing gzip/zstd/bzip2
+    #[arg(long)]
+    pub raw: bool,
 }
 
 #[derive(Debug, Parser)]
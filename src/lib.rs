@@ -2,6 +2,6 @@ mod cli;
 mod utils;
 mod process;
 
-pub use cli::{Opts, SubCommand,GenPassOpts, Base64SubCommand, Base64Format, TextSignFormat, TextSubCommand, HttpSubCommand};
-pub use process::{process_csv, process_genpass,process_decode, process_encode, process_text_sign, process_text_verify,process_generate,process_http_serve};
+pub use cli::{Opts, SubCommand, CsvSubCommand, FixedOpts, OutputFormat, XmlOpts,GenPassOpts, GenPassMode, GenPassSubCommand, ApiKeySubCommand, GenIdOpts, GenSubCommand, IdKind, PolicyOpts, StrengthOpts, Base64SubCommand, Base64Format, Codec, DecodeOpts, EncodeOpts, TextSignFormat, TextSubCommand, HttpSubCommand, OtpAlgorithm, OtpParams, OtpSubCommand, HashAlgorithm, HashPasswordOpts, HashSubCommand};
+pub use process::{process_csv, process_csv_batch, expand_inputs, process_fixed, process_melt, process_pivot, ColumnMasker, Records, SheetOpts, process_genpass, process_genpass_with_rng, process_genpass_words, PassphraseOpts, process_genpass_pattern, process_genpass_pronounceable, genpass_entropy, genpass_rng, PasswordPolicy, StrengthPolicy, BreachDb, strength_records, process_genpass_check, process_genpass_derive, process_gen_id, apikey_hash, process_apikey_generate, process_apikey_verify, Wordlist,process_decode, process_encode, process_codec_decode, process_codec_encode, process_text_sign, process_text_verify,process_generate,process_http_serve, process_otp_generate, process_otp_new, process_otp_verify, write_secret, process_hash_password, process_hash_verify, read_new_password};
 pub use utils::*;
@@ -1,5 +1,5 @@
 
-use std::fs;
+use std::{fs, io::{self, BufWriter, Write}, thread::available_parallelism};
 
 use clap::Parser;
 use anyhow::Result;
@@ -7,8 +7,8 @@ use zxcvbn::zxcvbn;
 
 
 // 导入 Opts、SubCommand 和 process_csv 函数
-use rcli::{process_csv, process_decode, process_encode, process_generate, process_genpass, process_text_sign, process_text_verify, process_http_serve,
-            Base64SubCommand, HttpSubCommand, Opts, SubCommand, TextSignFormat, TextSubCommand };
+use rcli::{expand_inputs, process_csv, process_csv_batch, process_fixed, process_melt, process_pivot, ColumnMasker, SheetOpts, StrengthPolicy, BreachDb, process_decode, process_encode, process_codec_decode, process_codec_encode, process_generate, genpass_entropy, genpass_rng, process_genpass_with_rng, process_genpass_words, PassphraseOpts, Wordlist, process_genpass_pattern, process_genpass_pronounceable, strength_records, process_genpass_check, process_genpass_derive, process_gen_id, apikey_hash, process_apikey_generate, process_apikey_verify, PasswordPolicy, process_text_sign, process_text_verify, process_http_serve, process_otp_generate, process_otp_new, process_otp_verify, write_secret, read_secret, write_binary, process_hash_password, process_hash_verify, read_new_password,
+            ApiKeySubCommand, Base64Format, Base64SubCommand, CsvSubCommand, HttpSubCommand, Opts, OtpSubCommand, XmlOpts, GenPassMode, GenPassSubCommand, GenSubCommand, HashSubCommand, SubCommand, TextSignFormat, TextSubCommand };
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -18,43 +18,161 @@ async fn main() -> Result<()> {
     
     // 根据子命令调用相应的处理函数
     match opts.cmd {
-        SubCommand::Csv(opts) =>{
-            let output = if let Some(output) = opts.output {
-                output.clone()
-            } else {
-                format!("output.{}", opts.format)
-            };
-            process_csv(&opts.input, output, opts.format)?;
+        SubCommand::Csv(opts) => match opts.cmd {
+            Some(CsvSubCommand::Pivot(opts)) => {
+                let output = opts.output.clone().unwrap_or_else(|| format!("output.{}", opts.format));
+                process_pivot(&opts, output)?;
+            }
+            Some(CsvSubCommand::Melt(opts)) => {
+                let output = opts.output.clone().unwrap_or_else(|| format!("output.{}", opts.format));
+                process_melt(&opts, output)?;
+            }
+            None => {
+                let inputs = expand_inputs(&opts.input)?;
+                let masker = ColumnMasker::try_new(opts.mask, opts.pseudonymize, opts.key.as_deref())?;
+                let sheet = SheetOpts {
+                    sheet: opts.sheet,
+                    header_row: opts.header_row,
+                    date_format: opts.date_format,
+                };
+                match (&opts.out_dir, inputs.as_slice()) {
+                    (Some(out_dir), _) => {
+                        let jobs = opts.jobs.unwrap_or_else(|| available_parallelism().map_or(1, |n| n.get()));
+                        process_csv_batch(&inputs, out_dir, jobs, opts.format, &masker, &sheet, &opts.xml)?;
+                    }
+                    (None, [input]) => {
+                        let output = if let Some(output) = opts.output {
+                            output.clone()
+                        } else {
+                            format!("output.{}", opts.format)
+                        };
+                        process_csv(input, output, opts.format, &masker, &sheet, &opts.xml)?;
+                    }
+                    (None, _) => anyhow::bail!("--out-dir is required when converting multiple inputs"),
+                }
+            }
+        },
+        SubCommand::Fixed(opts) => {
+            let output = opts.output.unwrap_or_else(|| format!("output.{}", opts.format));
+            process_fixed(&opts.input, &opts.layout, output, opts.format, &opts.xml)?;
         },
-        SubCommand::GenPass(opts) => {
-            let password =  process_genpass(opts.length, opts.uppercase, opts.lowercase, opts.number, opts.symbol)?;
-            println!("password: {}", password);
+        SubCommand::GenPass(opts) => match opts.cmd {
+            Some(GenPassSubCommand::Check(opts)) => {
+                let breach_db = opts.breach_db.as_deref().map(BreachDb::open).transpose()?;
+                let records = process_genpass_check(&opts.input, &opts.user_input, opts.show_password, breach_db.as_ref())?;
+                let content = records.serialize(opts.output_format, &XmlOpts::default())?;
+                println!("{}", content.trim_end());
+            }
+            Some(GenPassSubCommand::ApiKey(opts)) => match opts.cmd {
+                Some(ApiKeySubCommand::Verify(opts)) => {
+                    println!("{:?}", process_apikey_verify(&opts.key));
+                    if let Some(hash_key) = &opts.hash_key {
+                        println!("hash: {}", apikey_hash(&opts.key, hash_key)?);
+                    }
+                }
+                None => {
+                    let (key, hash) = process_apikey_generate(&opts.prefix, opts.bytes, opts.hash_key.as_deref())?;
+                    println!("key: {}", key);
+                    if let Some(hash) = hash {
+                        println!("hash: {}", hash);
+                    }
+                }
+            },
+            Some(GenPassSubCommand::Derive(opts)) => {
+                let master = read_secret(opts.master_file.as_deref(), "Master secret: ")?;
+                let policy = PasswordPolicy::try_from(&opts.policy)?;
+                let password = process_genpass_derive(&master, &opts.site, &opts.login, opts.counter, &policy, &StrengthPolicy::try_from(&opts.strength)?)?;
+                println!("password: {}", password);
+
+                let estimate = zxcvbn(&password, &[]);
+                eprintln!("Password strength: {}", estimate.score());
+                eprintln!("Entropy: {:.1} bits", genpass_entropy(&policy)?);
+            }
+            None => {
+                let strength = StrengthPolicy::try_from(&opts.strength)?;
+                let policy = PasswordPolicy::try_from(&opts.policy)?;
+                if opts.seed.is_some() {
+                    eprintln!("warning: --seed makes the passwords predictable, never use them as real credentials");
+                }
+                let mut rng = genpass_rng(opts.seed);
+                let passphrase = PassphraseOpts {
+                    count: opts.words.unwrap_or_default(),
+                    separator: opts.separator.clone(),
+                    capitalize: opts.capitalize,
+                    digits: opts.digits,
+                };
+                // loaded once for the whole batch
+                let wordlist = match opts.words {
+                    Some(_) => Some(opts.wordlist.as_deref().map_or_else(|| Ok(Wordlist::eff_large()), Wordlist::load)?),
+                    None => None,
+                };
+                let mut passwords = Vec::with_capacity(opts.count);
+                for _ in 0..opts.count {
+                    let generated = match (&wordlist, &opts.pattern, opts.mode) {
+                        (Some(wordlist), _, _) => process_genpass_words(&passphrase, wordlist, &policy, &strength, &mut rng)?,
+                        (None, Some(pattern), _) => process_genpass_pattern(pattern, &policy, &strength, &mut rng)?,
+                        (None, None, GenPassMode::Pronounceable) => process_genpass_pronounceable(&policy, &strength, &mut rng)?,
+                        (None, None, GenPassMode::Char) => (process_genpass_with_rng(&policy, &strength, &mut rng)?, genpass_entropy(&policy)?),
+                    };
+                    passwords.push(generated);
+                }
 
-            let estimate = zxcvbn(&password, &[]);
-            eprintln!("Password strength: {}", estimate.score());
+                match opts.output_format {
+                    Some(format) => {
+                        let content = strength_records(&passwords).serialize(format, &XmlOpts::default())?;
+                        println!("{}", content.trim_end());
+                    }
+                    None => {
+                        for (password, entropy) in &passwords {
+                            println!("password: {}", password);
 
+                            let estimate = zxcvbn(password, &[]);
+                            eprintln!("Password strength: {}", estimate.score());
+                            eprintln!("Entropy: {:.1} bits", entropy);
+                        }
+                    }
+                }
+            }
+        },
+        SubCommand::Gen(subcmd) => match subcmd {
+            GenSubCommand::Id(opts) => {
+                if opts.seed.is_some() {
+                    eprintln!("warning: --seed makes the ids predictable, only use them for fixtures");
+                }
+                for id in process_gen_id(&opts, &mut genpass_rng(opts.seed))? {
+                    println!("{}", id);
+                }
+            }
         },
         SubCommand::Base64(subcmd) => match subcmd {
             Base64SubCommand::Encode(opts) => {
-                let encoded = process_encode(&opts.input, opts.format)?;
-                println!("{}", encoded);
+                let mut stdout = BufWriter::new(io::stdout().lock());
+                process_encode(&opts.input, &mut stdout, opts.format, opts.raw)?;
+                // mime output already ends with CRLF
+                if !matches!(opts.format, Base64Format::Mime) {
+                    writeln!(stdout)?;
+                }
             }
             Base64SubCommand::Decode(opts) => {
-                let decoded = process_decode(&opts.input, opts.format)?;
-                
-                // TODO: decoded data might not be string(but for this example, we assume it is)
-                let decoded = String::from_utf8(decoded)?;
-                println!("{}", decoded);
+                write_binary(&opts.output, |writer| process_decode(&opts.input, writer, opts.format))?;
             }
         },
+        SubCommand::Encode(opts) => {
+            let mut stdout = BufWriter::new(io::stdout().lock());
+            process_codec_encode(&opts.input, &mut stdout, opts.codec)?;
+            writeln!(stdout)?;
+        }
+        SubCommand::Decode(opts) => {
+            write_binary(&opts.output, |writer| process_codec_decode(&opts.input, writer, opts.codec))?;
+        }
         SubCommand::Text(subcmd) => match subcmd {
             TextSubCommand::Sign(opts) => {
-                let sig = process_text_sign(&opts.input, &opts.key, opts.format)?;
+                let sig = process_text_sign(&opts.input, &opts.key, opts.format, opts.raw)?;
                 println!("{}", sig);
 
             }
             TextSubCommand::Verify(opts) => {
-                let verified =  process_text_verify(&opts.input, &opts.key, opts.format,&opts.sig)?;
+                let verified =  process_text_verify(&opts.input, &opts.key, opts.format,&opts.sig, opts.raw)?;
                 println!("{:?}", verified);
 
             }
@@ -78,6 +196,34 @@ async fn main() -> Result<()> {
                 process_http_serve(opts.dir, opts.port).await?;
             }
          }
+         SubCommand::Otp(subcmd) => match subcmd {
+            OtpSubCommand::Generate(opts) => {
+                let code = process_otp_generate(&opts.secret_file, &opts.params)?;
+                println!("{}", code);
+            }
+            OtpSubCommand::Verify(opts) => {
+                let verified = process_otp_verify(&opts.secret_file, &opts.code, opts.window, &opts.params)?;
+                println!("{:?}", verified);
+            }
+            OtpSubCommand::New(opts) => {
+                let (secret, uri) = process_otp_new(&opts.issuer, &opts.account, opts.bytes, &opts.params)?;
+                match &opts.output {
+                    Some(output) => write_secret(output, &secret)?,
+                    None => println!("secret: {}", secret),
+                }
+                println!("uri: {}", uri);
+            }
+         }
+         SubCommand::Hash(subcmd) => match subcmd {
+            HashSubCommand::Password(opts) => {
+                let password = read_new_password(opts.password_file.as_deref())?;
+                println!("{}", process_hash_password(&password, &opts)?);
+            }
+            HashSubCommand::Verify(opts) => {
+                let password = read_secret(opts.password_file.as_deref(), "Password: ")?;
+                println!("{:?}", process_hash_verify(&password, &opts.hash)?);
+            }
+         }
 }
 Ok(())
 }
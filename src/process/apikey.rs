@@ -0,0 +1,104 @@
+use anyhow::Result;
+
+use super::{
+    process_genpass,
+    text::{Blake3, KeyLoader},
+    PasswordPolicy, StrengthPolicy,
+};
+
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// a CRC32 always fits in 6 base62 characters (62^6 > 2^32)
+const CHECKSUM_LEN: usize = 6;
+
+/// generate `prefix_<base62 random><base62 crc32>` with `bytes` bytes of entropy,
+/// plus its keyed BLAKE3 hash when a key file is given so only the hash needs to be stored
+pub fn process_apikey_generate(prefix: &str, bytes: usize, hash_key: Option<&str>) -> Result<(String, Option<String>)> {
+    if prefix.is_empty() || !prefix.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        anyhow::bail!("Prefix must be non-empty and only contain ASCII letters, digits and '_'");
+    }
+    if bytes < 16 {
+        anyhow::bail!("API keys need at least 16 bytes (128 bits) of entropy");
+    }
+
+    // enough base62 characters to carry `bytes` bytes of entropy
+    let policy = PasswordPolicy {
+        length: (bytes as f64 * 8.0 / 62f64.log2()).ceil() as usize,
+        charset: Some(String::from_utf8(BASE62.to_vec())?),
+        min_upper: Some(0),
+        min_lower: Some(0),
+        min_number: Some(0),
+        ..Default::default()
+    };
+    let random = process_genpass(&policy, &StrengthPolicy::default())?;
+    let body = format!("{}_{}", prefix, random);
+    let key = format!("{}{}", body, checksum(&body));
+
+    let hash = hash_key.map(|path| apikey_hash(&key, path)).transpose()?;
+    Ok((key, hash))
+}
+
+/// check the embedded checksum, which catches typos and truncated keys without a server round trip
+pub fn process_apikey_verify(key: &str) -> bool {
+    // generated keys are ASCII, anything else can't be one, and ASCII can be split at any byte
+    if !key.is_ascii() {
+        return false;
+    }
+    let Some(split) = key.len().checked_sub(CHECKSUM_LEN) else {
+        return false;
+    };
+    let (body, sum) = key.split_at(split);
+    let Some((prefix, random)) = body.rsplit_once('_') else {
+        return false;
+    };
+    !prefix.is_empty() && !random.is_empty() && random.bytes().all(|b| BASE62.contains(&b)) && checksum(body) == sum
+}
+
+/// keyed BLAKE3 hash of an API key, safe to store server-side
+pub fn apikey_hash(key: &str, hash_key: &str) -> Result<String> {
+    let hasher = Blake3::load(hash_key)?;
+    Ok(hasher.keyed_hash(key.as_bytes()).to_hex().to_string())
+}
+
+fn checksum(body: &str) -> String {
+    let mut crc = crc32fast::hash(body.as_bytes());
+    let mut sum = [b'0'; CHECKSUM_LEN];
+    for c in sum.iter_mut().rev() {
+        *c = BASE62[(crc % 62) as usize];
+        crc /= 62;
+    }
+    String::from_utf8_lossy(&sum).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apikey_roundtrip() -> Result<()> {
+        let (key, hash) = process_apikey_generate("rcli_live", 32, Some("fixtures/blake3.txt"))?;
+        assert!(key.starts_with("rcli_live_"));
+        // 256 bits need 43 base62 characters
+        assert_eq!(key.len(), "rcli_live_".len() + 43 + CHECKSUM_LEN);
+        assert!(process_apikey_verify(&key));
+        assert_eq!(hash, Some(apikey_hash(&key, "fixtures/blake3.txt")?));
+
+        let mut typo = key.clone().into_bytes();
+        typo[12] = if typo[12] == b'a' { b'b' } else { b'a' };
+        assert!(!process_apikey_verify(&String::from_utf8(typo)?));
+        assert!(!process_apikey_verify(&key[..key.len() - 1]));
+        assert!(!process_apikey_verify("short"));
+        assert!(!process_apikey_verify("€€€a"));
+        assert!(!process_apikey_verify("rcli_abc€3jZRM"));
+
+        assert!(process_apikey_generate("bad-prefix", 32, None).is_err());
+        assert!(process_apikey_generate("rcli", 8, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum() {
+        // crc32("123456789") = 0xCBF43926 = 3421780262
+        assert_eq!(checksum("123456789"), "3jZRME");
+    }
+}
@@ -1,53 +1,302 @@
+use std::io::{self, Read, Write};
+
 use base64::{
-    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine as _
-    };
-use crate::{get_reader, Base64Format};
+    alphabet,
+    engine::{
+        general_purpose::{GeneralPurpose, GeneralPurposeConfig, NO_PAD, STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+        DecodePaddingMode,
+    },
+    read::DecoderReader,
+    write::EncoderWriter,
+    Engine as _,
+};
+use crate::{get_raw_reader, get_reader, Base64Format};
 use anyhow::Result;
 
-pub fn process_encode(input: &str, format: Base64Format) -> Result<String> {
+const CRYPT: GeneralPurpose = GeneralPurpose::new(&alphabet::CRYPT, NO_PAD);
+const BCRYPT: GeneralPurpose = GeneralPurpose::new(&alphabet::BCRYPT, NO_PAD);
+/// `--format auto` accepts input with or without padding
+const INDIFFERENT: GeneralPurposeConfig = GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const STANDARD_INDIFFERENT: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, INDIFFERENT);
+const URL_SAFE_INDIFFERENT: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, INDIFFERENT);
+
+/// line length for `--format mime`, RFC 2045 section 6.8
+const MIME_LINE_LEN: usize = 76;
+
+/// encode `input` into `writer` chunk by chunk, so memory use doesn't grow with the input;
+/// compressed input is decompressed first unless `raw` is set
+pub fn process_encode(input: &str, writer: &mut dyn Write, format: Base64Format, raw: bool) -> Result<()> {
+    let reader = if raw { get_raw_reader(input)? } else { get_reader(input)? };
+    match format {
+        Base64Format::Auto => anyhow::bail!("--format auto only works when decoding"),
+        Base64Format::Mime => {
+            // every line ends with CRLF, including the last one
+            let wrap = encode_to(reader, LineWrap::new(writer, MIME_LINE_LEN), engine(format))?;
+            if wrap.column > 0 {
+                wrap.inner.write_all(b"\r\n")?;
+            }
+            Ok(())
+        }
+        _ => encode_to(reader, writer, engine(format)).map(|_| ()),
+    }
+}
+
+fn encode_to<W: Write>(mut reader: impl Read, writer: W, engine: &GeneralPurpose) -> Result<W> {
+    let mut encoder = EncoderWriter::new(writer, engine);
+    io::copy(&mut reader, &mut encoder)?;
+    Ok(encoder.finish()?)
+}
+
+/// decode `input` into `writer` chunk by chunk, whitespace around the data is ignored;
+/// mime and auto ignore whitespace anywhere, and auto reads the whole input to detect the alphabet
+pub fn process_decode(input: &str, writer: &mut dyn Write, format: Base64Format) -> Result<()> {
+    let reader = get_reader(input)?;
+    match format {
+        Base64Format::Auto => {
+            let mut data = Vec::new();
+            Unwrapped(reader).read_to_end(&mut data)?;
+            writer.write_all(&detect(&data)?.decode(&data)?)?;
+        }
+        Base64Format::Mime => decode_unwrapped(reader, writer, format)?,
+        _ => {
+            io::copy(&mut DecoderReader::new(Trimmed::new(reader), engine(format)), writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// decode ignoring whitespace anywhere, still streaming
+pub(super) fn decode_unwrapped(reader: impl Read, writer: &mut dyn Write, format: Base64Format) -> Result<()> {
+    io::copy(&mut DecoderReader::new(Unwrapped(reader), engine(format)), writer)?;
+    Ok(())
+}
+
+fn engine(format: Base64Format) -> &'static GeneralPurpose {
+    match format {
+        Base64Format::Standard | Base64Format::Mime => &STANDARD,
+        Base64Format::StandardNoPad => &STANDARD_NO_PAD,
+        Base64Format::UrlSafe => &URL_SAFE_NO_PAD,
+        Base64Format::UrlSafePad => &URL_SAFE,
+        Base64Format::Crypt => &CRYPT,
+        Base64Format::Bcrypt => &BCRYPT,
+        // only used once `detect` has looked at the data
+        Base64Format::Auto => &STANDARD_INDIFFERENT,
+    }
+}
+
+/// pick the standard or URL-safe alphabet from the characters that only one of them uses
+fn detect(data: &[u8]) -> Result<&'static GeneralPurpose> {
+    if data.contains(&b'.') {
+        anyhow::bail!("Input looks like crypt or bcrypt base64, which can't be told apart, use --format crypt or --format bcrypt");
+    }
+    let standard = data.iter().any(|b| matches!(b, b'+' | b'/'));
+    let url_safe = data.iter().any(|b| matches!(b, b'-' | b'_'));
+    match (standard, url_safe) {
+        (true, true) => anyhow::bail!("Input mixes the standard and URL-safe base64 alphabets"),
+        (false, true) => Ok(&URL_SAFE_INDIFFERENT),
+        _ => Ok(&STANDARD_INDIFFERENT),
+    }
+}
+
+/// breaks the output into CRLF-separated lines of `width` bytes
+struct LineWrap<W> {
+    inner: W,
+    width: usize,
+    column: usize,
+}
+
+impl<W: Write> LineWrap<W> {
+    fn new(inner: W, width: usize) -> Self {
+        Self { inner, width, column: 0 }
+    }
+}
+
+impl<W: Write> Write for LineWrap<W> {
+    // takes the whole buffer, EncoderWriter treats short writes from its delegate as stalls
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(b"\r\n")?;
+                self.column = 0;
+            }
+            let len = rest.len().min(self.width - self.column);
+            self.inner.write_all(&rest[..len])?;
+            self.column += len;
+            rest = &rest[len..];
+        }
+        Ok(buf.len())
+    }
 
-    let mut reader= get_reader(input)?;
-    let mut buf = Vec::new();
-    reader. read_to_end(&mut buf)?;
-    let encoded = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buf),
-        
-    };
-    Ok(encoded)
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> Result<Vec<u8>> {
-    let mut reader= get_reader(input)?;
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    let buf = buf.trim();
+/// drops whitespace anywhere in the input, for wrapped or pasted base64
+struct Unwrapped<R>(R);
 
+impl<R: Read> Read for Unwrapped<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.0.read(out)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                if !out[i].is_ascii_whitespace() {
+                    out[len] = out[i];
+                    len += 1;
+                }
+            }
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
 
-    let decoded = match format {
-        Base64Format::Standard => STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buf)?,
-    };
+/// skips leading whitespace and holds back whitespace until more data follows it,
+/// so trailing whitespace never reaches the decoder while embedded whitespace still does
+struct Trimmed<R> {
+    inner: R,
+    started: bool,
+    held: Vec<u8>,
+    buf: Vec<u8>,
+}
 
-    Ok(decoded)
+impl<R: Read> Trimmed<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, started: false, held: Vec::new(), buf: vec![0; 8 * 1024] }
+    }
 }
 
+impl<R: Read> Read for Trimmed<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // everything up to the last non-whitespace byte is safe to pass on
+            let ready = self.held.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |i| i + 1);
+            if ready > 0 || out.is_empty() {
+                let len = ready.min(out.len());
+                out[..len].copy_from_slice(&self.held[..len]);
+                self.held.drain(..len);
+                return Ok(len);
+            }
+            let n = self.inner.read(&mut self.buf)?;
+            if n == 0 {
+                // EOF: whatever is still held was trailing whitespace
+                return Ok(0);
+            }
+            for &b in &self.buf[..n] {
+                self.started |= !b.is_ascii_whitespace();
+                if self.started {
+                    self.held.push(b);
+                }
+            }
+        }
+    }
+}
 
-#[cfg(test)] 
+
+#[cfg(test)]
 mod test {
     use super::*;
+    use crate::utils::test_support::TempFile;
+
+    #[test]
+    fn test_process_encode() -> Result<()> {
+        let mut encoded = Vec::new();
+        process_encode("Cargo.toml", &mut encoded, Base64Format::Standard, false)?;
+        assert_eq!(encoded, STANDARD.encode(std::fs::read("Cargo.toml")?).as_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_compressed_input() -> Result<()> {
+        let input = TempFile::new("b64_encode.gz");
+        let mut writer = crate::get_writer(input.path())?;
+        writer.write_all(b"hello world")?;
+        writer.finish()?;
+
+        let mut encoded = Vec::new();
+        process_encode(input.path(), &mut encoded, Base64Format::Standard, false)?;
+        assert_eq!(encoded, STANDARD.encode(b"hello world").as_bytes());
+        let mut encoded = Vec::new();
+        process_encode(input.path(), &mut encoded, Base64Format::Standard, true)?;
+        assert_eq!(encoded, STANDARD.encode(std::fs::read(input.path())?).as_bytes());
+        Ok(())
+    }
 
     #[test]
-    fn test_process_encode() {
-        let input = "Cargo.toml";
-        let format = Base64Format::Standard;
-        assert!(process_encode(input, format).is_ok());
+    fn test_process_decode() -> Result<()> {
+        let mut decoded = Vec::new();
+        process_decode("fixtures/test1.txt", &mut decoded, Base64Format::UrlSafe)?;
+        let expected = URL_SAFE_NO_PAD.decode(std::fs::read_to_string("fixtures/test1.txt")?.trim())?;
+        assert_eq!(decoded, expected);
+        Ok(())
     }
 
     #[test]
-    fn test_process_decode() {
-        let input = "fixtures/test1.txt";
-        let format = Base64Format::UrlSafe;
-        process_decode(input, format).unwrap();
+    fn test_trimmed_reader() -> Result<()> {
+        // bigger than every internal buffer, with whitespace on both ends
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let padded = format!(" \n{}\r\n\n", STANDARD.encode(&data));
+        let mut decoded = Vec::new();
+        DecoderReader::new(Trimmed::new(padded.as_bytes()), &STANDARD).read_to_end(&mut decoded)?;
+        assert_eq!(decoded, data);
+
+        // embedded whitespace is still invalid
+        let mut decoded = Vec::new();
+        assert!(DecoderReader::new(Trimmed::new(&b"aGVs bG8="[..]), &STANDARD).read_to_end(&mut decoded).is_err());
+        Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_formats_roundtrip() -> Result<()> {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let input = TempFile::with_data("b64_formats.bin", &data)?;
+        let formats = ["standard", "standard-nopad", "urlsafe", "urlsafe-pad", "mime", "crypt", "bcrypt"];
+        for format in formats {
+            let format: Base64Format = format.parse()?;
+            let mut encoded = Vec::new();
+            process_encode(input.path(), &mut encoded, format, false)?;
+            let encoded_input = TempFile::with_data(&format!("b64_formats.{}", format), &encoded)?;
+            let mut decoded = Vec::new();
+            process_decode(encoded_input.path(), &mut decoded, format)?;
+            assert_eq!(decoded, data, "{}", format);
+        }
+        assert!(process_encode(input.path(), &mut Vec::new(), Base64Format::Auto, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mime_wrapping() -> Result<()> {
+        let input = TempFile::with_data("b64_mime.bin", &[0xab; 120])?;
+        let mut encoded = Vec::new();
+        process_encode(input.path(), &mut encoded, Base64Format::Mime, false)?;
+        let encoded = String::from_utf8(encoded)?;
+        let lines: Vec<&str> = encoded.split_terminator("\r\n").collect();
+        assert!(encoded.ends_with("\r\n") && !encoded.ends_with("\r\n\r\n"));
+        assert_eq!(lines.iter().map(|line| line.len()).collect::<Vec<_>>(), [76, 76, 8]);
+        assert_eq!(lines.concat(), STANDARD.encode([0xab; 120]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_detect() -> Result<()> {
+        let data = [0xfb, 0xff, 0xfe, 0x68, 0x69];
+        // wrapped and indented, padded or not, in either alphabet
+        for encoded in ["  +//+\r\naGk=\n", "-__-aGk", "-__-\n  aGk=\n"] {
+            let input = TempFile::with_data("b64_auto.txt", encoded.as_bytes())?;
+            let mut decoded = Vec::new();
+            process_decode(input.path(), &mut decoded, Base64Format::Auto)?;
+            assert_eq!(decoded, data, "{}", encoded);
+        }
+        for encoded in ["+/_-", "ab.c"] {
+            let input = TempFile::with_data("b64_auto_bad.txt", encoded.as_bytes())?;
+            assert!(process_decode(input.path(), &mut Vec::new(), Base64Format::Auto).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::{cmp::Ordering, fs::File, path::Path};
+
+use anyhow::Result;
+use memmap2::Mmap;
+use sha1::{Digest, Sha1};
+
+/// length of an uppercase hex SHA-1 at the start of every line
+const HASH_LEN: usize = 40;
+
+/// a local Have I Been Pwned dump (`pwned-passwords-sha1-ordered-by-hash.txt`),
+/// lines of `SHA1:COUNT` sorted by hash, searched in place without loading it into memory
+#[derive(Debug)]
+pub struct BreachDb {
+    data: Mmap,
+}
+
+impl BreachDb {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the dump is only read, and isn't expected to change while rcli runs
+        let data = unsafe { Mmap::map(&file)? };
+        Ok(Self { data })
+    }
+
+    /// how often `password` appears in the dump, 0 if it was never breached
+    pub fn count(&self, password: &str) -> u64 {
+        let hash: String = Sha1::digest(password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect();
+        self.find(hash.as_bytes())
+    }
+
+    /// binary search by byte offset, snapping every probe to the start of its line
+    fn find(&self, hash: &[u8]) -> u64 {
+        let data = &self.data[..];
+        // `lo` always sits at the start of a line
+        let (mut lo, mut hi) = (0, data.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let start = data[lo..mid].iter().rposition(|b| *b == b'\n').map_or(lo, |i| lo + i + 1);
+            let end = data[start..].iter().position(|b| *b == b'\n').map_or(data.len(), |i| start + i);
+            let line = &data[start..end];
+            let line_hash = &line[..HASH_LEN.min(line.len())];
+
+            match line_hash.to_ascii_uppercase().as_slice().cmp(hash) {
+                Ordering::Equal => return parse_count(line),
+                Ordering::Less => lo = end + 1,
+                Ordering::Greater => hi = start,
+            }
+        }
+        0
+    }
+}
+
+/// the count after the colon, a hash without one still counts as breached
+fn parse_count(line: &[u8]) -> u64 {
+    std::str::from_utf8(&line[HASH_LEN..])
+        .ok()
+        .and_then(|rest| rest.trim().strip_prefix(':'))
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breach_db() -> Result<()> {
+        let db = BreachDb::open("fixtures/pwned-passwords-sample.txt")?;
+        assert_eq!(db.count("password"), 10434004);
+        assert_eq!(db.count("123456"), 46980896);
+        assert_eq!(db.count("hunter2"), 35077);
+        assert_eq!(db.count("Kx9#mQ2$vL7@pW4!"), 0);
+
+        // every line is reachable, including the first and the last
+        for line in std::fs::read_to_string("fixtures/pwned-passwords-sample.txt")?.lines() {
+            let (hash, count) = line.split_once(':').unwrap();
+            assert_eq!(db.find(hash.as_bytes()), count.parse::<u64>()?);
+        }
+        assert_eq!(db.find(&[b'0'; HASH_LEN]), 0);
+        assert_eq!(db.find(&[b'F'; HASH_LEN]), 0);
+        Ok(())
+    }
+}
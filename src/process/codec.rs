@@ -0,0 +1,227 @@
+use std::io::{Read, Write};
+
+use anyhow::Result;
+use data_encoding::{Encoding, BASE32, BASE32HEX, HEXLOWER_PERMISSIVE};
+
+use crate::{cli::Codec, get_raw_reader, get_reader, Base64Format};
+
+use super::{b64::decode_unwrapped, process_encode};
+
+/// digits of Ascii85 are `!` (0) to `u` (84), `z` stands for a group of four zero bytes
+const ASCII85_ZERO: u8 = b'z';
+const Z85_ALPHABET: &[u8; 85] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// encode `input` into `writer`; base64 streams, the other codecs read the whole input first
+pub fn process_codec_encode(input: &str, writer: &mut dyn Write, codec: Codec) -> Result<()> {
+    // key bytes are random, so they must never be mistaken for compressed input
+    if let Some(format) = base64_format(codec) {
+        return process_encode(input, writer, format, true);
+    }
+    let mut data = Vec::new();
+    get_raw_reader(input)?.read_to_end(&mut data)?;
+    let encoded = match codec {
+        Codec::Hex => HEXLOWER_PERMISSIVE.encode(&data),
+        Codec::Base32 => BASE32.encode(&data),
+        Codec::Base32Hex => BASE32HEX.encode(&data),
+        Codec::Base58 => bs58::encode(&data).into_string(),
+        Codec::Base58Check => bs58::encode(&data).with_check().into_string(),
+        Codec::Base85 => encode85(&data, &ascii85_alphabet(), true),
+        Codec::Z85 => {
+            if !data.len().is_multiple_of(4) {
+                anyhow::bail!("Z85 input must be a multiple of 4 bytes, got {}", data.len());
+            }
+            encode85(&data, Z85_ALPHABET, false)
+        }
+        Codec::Base64 | Codec::Base64Url => unreachable!("base64 is handled above"),
+    };
+    writer.write_all(encoded.as_bytes())?;
+    Ok(())
+}
+
+/// decode `input` into `writer`, whitespace anywhere in the input is ignored
+pub fn process_codec_decode(input: &str, writer: &mut dyn Write, codec: Codec) -> Result<()> {
+    if let Some(format) = base64_format(codec) {
+        return decode_unwrapped(get_reader(input)?, writer, format);
+    }
+    let mut text = String::new();
+    get_reader(input)?.read_to_string(&mut text)?;
+    let text: String = text.split_whitespace().collect();
+    let decoded = match codec {
+        Codec::Hex => decode_with(&HEXLOWER_PERMISSIVE, &text, codec)?,
+        Codec::Base32 => decode_with(&BASE32, &text.to_uppercase(), codec)?,
+        Codec::Base32Hex => decode_with(&BASE32HEX, &text.to_uppercase(), codec)?,
+        Codec::Base58 => bs58::decode(&text).into_vec().map_err(|e| anyhow::anyhow!("Invalid base58: {}", e))?,
+        Codec::Base58Check => bs58::decode(&text)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| anyhow::anyhow!("Invalid base58check: {}", e))?,
+        Codec::Base85 => decode85(text.as_bytes(), &ascii85_alphabet(), true)?,
+        Codec::Z85 => {
+            if !text.len().is_multiple_of(5) {
+                anyhow::bail!("Z85 input must be a multiple of 5 characters, got {}", text.len());
+            }
+            decode85(text.as_bytes(), Z85_ALPHABET, false)?
+        }
+        Codec::Base64 | Codec::Base64Url => unreachable!("base64 is handled above"),
+    };
+    writer.write_all(&decoded)?;
+    Ok(())
+}
+
+fn base64_format(codec: Codec) -> Option<Base64Format> {
+    match codec {
+        Codec::Base64 => Some(Base64Format::Standard),
+        Codec::Base64Url => Some(Base64Format::UrlSafe),
+        _ => None,
+    }
+}
+
+fn decode_with(encoding: &Encoding, text: &str, codec: Codec) -> Result<Vec<u8>> {
+    encoding
+        .decode(text.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", codec, e))
+}
+
+fn ascii85_alphabet() -> [u8; 85] {
+    std::array::from_fn(|i| b'!' + i as u8)
+}
+
+/// every 4 bytes become 5 base-85 digits, most significant first; a short last group of
+/// n bytes is zero-padded and only its first n + 1 digits are kept
+fn encode85(data: &[u8], alphabet: &[u8; 85], zero_shortcut: bool) -> String {
+    let mut encoded = Vec::with_capacity(data.len().div_ceil(4) * 5);
+    for chunk in data.chunks(4) {
+        if zero_shortcut && chunk == [0; 4] {
+            encoded.push(ASCII85_ZERO);
+            continue;
+        }
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = alphabet[(value % 85) as usize];
+            value /= 85;
+        }
+        encoded.extend_from_slice(&digits[..chunk.len() + 1]);
+    }
+    String::from_utf8(encoded).expect("alphabets are ASCII")
+}
+
+/// the reverse of `encode85`, a short last group is padded with the highest digit
+fn decode85(text: &[u8], alphabet: &[u8; 85], zero_shortcut: bool) -> Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(text.len() / 5 * 4 + 4);
+    let mut group = Vec::with_capacity(5);
+    for (i, &c) in text.iter().enumerate() {
+        if zero_shortcut && c == ASCII85_ZERO {
+            if !group.is_empty() {
+                anyhow::bail!("Invalid base85: 'z' inside a group at position {}", i);
+            }
+            decoded.extend_from_slice(&[0; 4]);
+            continue;
+        }
+        let digit = alphabet
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| anyhow::anyhow!("Invalid base85: unexpected character {:?} at position {}", c as char, i))?;
+        group.push(digit as u8);
+        if group.len() == 5 {
+            decoded.extend_from_slice(&decode_group(&group)?);
+            group.clear();
+        }
+    }
+    match group.len() {
+        0 => {}
+        1 => anyhow::bail!("Invalid base85: a final group needs at least 2 characters"),
+        n => {
+            group.resize(5, 84);
+            decoded.extend_from_slice(&decode_group(&group)?[..n - 1]);
+        }
+    }
+    Ok(decoded)
+}
+
+fn decode_group(digits: &[u8]) -> Result<[u8; 4]> {
+    let value = digits.iter().fold(0u64, |value, digit| value * 85 + *digit as u64);
+    let value = u32::try_from(value).map_err(|_| anyhow::anyhow!("Invalid base85: group overflows 32 bits"))?;
+    Ok(value.to_be_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::TempFile;
+
+    fn encode(data: &[u8], codec: Codec) -> Result<String> {
+        let input = TempFile::with_data(&format!("codec_encode.{}", codec), data)?;
+        let mut encoded = Vec::new();
+        process_codec_encode(input.path(), &mut encoded, codec)?;
+        Ok(String::from_utf8(encoded)?)
+    }
+
+    fn decode(text: &str, codec: Codec) -> Result<Vec<u8>> {
+        let input = TempFile::with_data(&format!("codec_decode.{}", codec), text.as_bytes())?;
+        let mut decoded = Vec::new();
+        process_codec_decode(input.path(), &mut decoded, codec)?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn test_known_vectors() -> Result<()> {
+        let cases: [(&[u8], Codec, &str); 9] = [
+            (b"foobar", Codec::Hex, "666f6f626172"),
+            (b"foobar", Codec::Base32, "MZXW6YTBOI======"),
+            (b"foobar", Codec::Base32Hex, "CPNMUOJ1E8======"),
+            (b"Hello World!", Codec::Base58, "2NEpo7TZRRrLZSi2U"),
+            (b"Man is distinguished", Codec::Base85, "9jqo^BlbD-BleB1DJ+*+F(f,q"),
+            (b"\0\0\0\0abc", Codec::Base85, "z@:E^"),
+            (b"\xff\xff\xff\xff", Codec::Base85, "s8W-!"),
+            // ZeroMQ RFC 32 test vector
+            (&[0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B], Codec::Z85, "HelloWorld"),
+            (b"foobar", Codec::Base64Url, "Zm9vYmFy"),
+        ];
+        for (data, codec, text) in cases {
+            assert_eq!(encode(data, codec)?, text, "{}", codec);
+            assert_eq!(decode(text, codec)?, data, "{}", codec);
+        }
+        // case and whitespace don't matter when decoding
+        assert_eq!(decode("66 6F\n6f626172\n", Codec::Hex)?, b"foobar");
+        assert_eq!(decode("mzxw6ytb\noi======", Codec::Base32)?, b"foobar");
+        assert_eq!(decode("aGVs\nbG8=\n", Codec::Base64)?, b"hello");
+        assert_eq!(decode(" Zm9v\r\nYmFy ", Codec::Base64Url)?, b"foobar");
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip_and_errors() -> Result<()> {
+        let data: Vec<u8> = (0..=255u8).rev().collect();
+        let codecs = ["base64", "base64url", "hex", "base32", "base32hex", "base58", "base58check", "base85", "z85"];
+        for codec in codecs {
+            let codec: Codec = codec.parse()?;
+            assert_eq!(decode(&encode(&data, codec)?, codec)?, data, "{}", codec);
+        }
+
+        let checked = encode(b"key material", Codec::Base58Check)?;
+        assert_eq!(decode(&checked, Codec::Base58Check)?, b"key material");
+        // plain base58 keeps the 4 checksum bytes at the end
+        let raw = decode(&checked, Codec::Base58)?;
+        assert_eq!((raw.len(), &raw[..12]), (16, &b"key material"[..]));
+        let mut tampered = checked.into_bytes();
+        tampered[2] = if tampered[2] == b'2' { b'3' } else { b'2' };
+        assert!(decode(std::str::from_utf8(&tampered)?, Codec::Base58Check).is_err());
+
+        // random keys sometimes start with compression magic, they are still encoded as is
+        let gzip_like = [0x1f, 0x8b, 0x08, 0x00, 0xde, 0xad, 0xbe, 0xef];
+        for codec in [Codec::Hex, Codec::Base64] {
+            assert_eq!(decode(&encode(&gzip_like, codec)?, codec)?, gzip_like, "{}", codec);
+        }
+        assert_eq!(encode(&gzip_like, Codec::Hex)?, "1f8b0800deadbeef");
+
+        assert!(encode(b"abc", Codec::Z85).is_err());
+        assert!(decode("ab{cd", Codec::Base85).is_err());
+        assert!(decode("s8W-\"", Codec::Base85).is_err());
+        assert!(decode("a", Codec::Base85).is_err());
+        assert!(decode("0g", Codec::Hex).is_err());
+        Ok(())
+    }
+}
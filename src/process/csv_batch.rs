@@ -0,0 +1,148 @@
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{atomic::{AtomicUsize, Ordering}, mpsc},
+    thread,
+};
+
+use crate::cli::{OutputFormat, XmlOpts};
+
+use super::{process_csv, ColumnMasker, SheetOpts};
+
+const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "zst", "bz2"];
+
+/// 展开输入中的 glob 模式，已存在的文件和 "-" 原样保留
+pub fn expand_inputs(inputs: &[String]) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input == "-" || Path::new(input).is_file() {
+            files.push(input.clone());
+            continue;
+        }
+        let mut matched: Vec<PathBuf> = glob::glob(input)?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|p| p.is_file())
+            .collect();
+        if matched.is_empty() {
+            anyhow::bail!("No files match: {}", input);
+        }
+        matched.sort();
+        files.extend(matched.into_iter().map(|p| p.to_string_lossy().into_owned()));
+    }
+    Ok(files)
+}
+
+/// 使用 `jobs` 个工作线程并发转换多个文件，输出到 `out_dir`，最后打印每个文件的结果
+pub fn process_csv_batch(
+    inputs: &[String],
+    out_dir: &Path,
+    jobs: usize,
+    format: OutputFormat,
+    masker: &ColumnMasker,
+    sheet: &SheetOpts,
+    xml: &XmlOpts,
+) -> Result<()> {
+    std::fs::create_dir_all(out_dir)?;
+    let outputs = inputs
+        .iter()
+        .map(|input| output_path(input, out_dir, format))
+        .collect::<Result<Vec<_>>>()?;
+    // 不同目录下的同名文件会写到同一个输出，提前报错而不是让工作线程互相覆盖
+    let mut seen = HashMap::new();
+    for (input, output) in inputs.iter().zip(&outputs) {
+        if let Some(other) = seen.insert(output, input) {
+            anyhow::bail!("{} and {} would both be written to {}", other, input, output);
+        }
+    }
+
+    // 工作线程从共享的下标中领取任务，结果通过 channel 送回
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|s| {
+        for _ in 0..jobs.clamp(1, inputs.len().max(1)) {
+            let tx = tx.clone();
+            let (next, outputs) = (&next, &outputs);
+            s.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(input) = inputs.get(i) else { break };
+                let ret = process_csv(input, outputs[i].clone(), format, masker, sheet, xml);
+                tx.send((i, ret)).expect("receiver outlives workers");
+            });
+        }
+    });
+    drop(tx);
+
+    let mut results: Vec<_> = rx.into_iter().collect();
+    results.sort_by_key(|(i, _)| *i);
+    let mut failed = 0;
+    for (i, ret) in results {
+        match ret {
+            Ok(()) => eprintln!("ok      {} -> {}", inputs[i], outputs[i]),
+            Err(e) => {
+                failed += 1;
+                eprintln!("FAILED  {}: {:#}", inputs[i], e);
+            }
+        }
+    }
+    eprintln!("{} succeeded, {} failed", inputs.len() - failed, failed);
+    if failed > 0 {
+        anyhow::bail!("{} of {} files failed to convert", failed, inputs.len());
+    }
+    Ok(())
+}
+
+/// 输出文件名为去掉最后一个扩展名（以及压缩扩展名）后的文件名加上输出格式
+fn output_path(input: &str, out_dir: &Path, format: OutputFormat) -> Result<String> {
+    let mut path = Path::new(input);
+    let stem = loop {
+        let stem = path
+            .file_stem()
+            .ok_or_else(|| anyhow::anyhow!("Invalid input file name: {}", input))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if COMPRESSED_EXTENSIONS.contains(&ext) => path = Path::new(stem),
+            _ => break stem,
+        }
+    };
+    // 直接拼接扩展名，避免 with_extension 替换掉 data.2024 中的 .2024
+    let mut name = stem.to_os_string();
+    name.push(format!(".{}", format));
+    let output = out_dir.join(name);
+    Ok(output.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_support::TempDir;
+
+    #[test]
+    fn test_expand_inputs() -> Result<()> {
+        let files = expand_inputs(&["fixtures/test1.txt.*".into(), "Cargo.toml".into()])?;
+        assert_eq!(files, ["fixtures/test1.txt.bz2", "fixtures/test1.txt.gz", "fixtures/test1.txt.zst", "Cargo.toml"]);
+        assert!(expand_inputs(&["fixtures/*.none".into()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_batch() -> Result<()> {
+        let out_dir = TempDir::new("csv_batch");
+        let out_dir = out_dir.path();
+        let inputs = ["assets/juventus.csv".to_string(), "fixtures/missing.csv".to_string()];
+        let masker = ColumnMasker::default();
+        let ret = process_csv_batch(&inputs, out_dir, 2, OutputFormat::Yaml, &masker, &SheetOpts::default(), &XmlOpts::default());
+
+        // missing.csv 不存在，批量转换应当报错，但其他文件仍然被转换
+        assert!(ret.is_err());
+        assert!(out_dir.join("juventus.yaml").exists());
+        assert_eq!(output_path("exports/a.csv.gz", out_dir, OutputFormat::Json)?, out_dir.join("a.json").to_string_lossy());
+        assert_eq!(output_path("data.2024.csv", out_dir, OutputFormat::Json)?, out_dir.join("data.2024.json").to_string_lossy());
+
+        // 同名输入在启动工作线程之前就报错
+        let inputs = ["assets/juventus.csv".to_string(), "exports/juventus.csv".to_string()];
+        let ret = process_csv_batch(&inputs, out_dir, 2, OutputFormat::Yaml, &masker, &SheetOpts::default(), &XmlOpts::default());
+        assert!(ret.unwrap_err().to_string().contains("would both be written to"));
+        Ok(())
+    }
+}
@@ -1,52 +1,143 @@
 
-use csv::Reader;
-use serde::{Deserialize, Serialize};
-use std::fs;
+use csv::{Reader, Writer};
+use serde_json::Value;
 use anyhow::Result;
+use std::io::Write;
 
-use crate::cli::OutputFormat;
-
-/// 表示 CSV 文件中球员的结构体
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct Player {
-    name: String,  
-    position: String,
-    #[serde(rename = "DOB")]
-    dob: String,
-    nationality: String,
-    #[serde(rename = "Kit Number")]
-    kit: u8,
+use crate::{
+    cli::{OutputFormat, XmlOpts},
+    get_reader, get_writer,
+};
+
+use super::{
+    excel::{is_spreadsheet, read_sheet},
+    xml::{is_xml, read_xml, to_xml},
+    ColumnMasker, SheetOpts,
+};
+
+/// 按表头顺序保存的表格数据，可以序列化为任意 OutputFormat
+#[derive(Debug, Default)]
+pub struct Records {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
 }
 
-/// 处理 CSV 文件并将其转换为 JSON 文件的函数
-pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
-    // 创建 CSV 读取器
-    let mut reader = Reader::from_path(input)?;
+/// 处理 CSV 文件并将其转换为 JSON 文件的函数，.xlsx/.xls/.ods 等表格文件会按工作表读取，.xml 文件按行元素读取
+pub fn process_csv(
+    input: &str,
+    output: String,
+    format: OutputFormat,
+    masker: &ColumnMasker,
+    sheet: &SheetOpts,
+    xml: &XmlOpts,
+) -> Result<()> {
+    let mut records = read_records(input, sheet)?;
+    masker.mask_records(&mut records)?;
+    records.write(&output, format, xml)
+}
 
-    // 用于存储 CSV 记录的 Vec
-    let mut ret = Vec::with_capacity(128);
+/// 根据输入文件类型读取 CSV、XML 或表格文件
+pub(crate) fn read_records(input: &str, sheet: &SheetOpts) -> Result<Records> {
+    if is_spreadsheet(input) {
+        read_sheet(input, sheet)
+    } else if is_xml(input) {
+        read_xml(input)
+    } else {
+        read_csv(input)
+    }
+}
 
-    let headers = reader.headers()?.clone();
+fn read_csv(input: &str) -> Result<Records> {
+    // 创建 CSV 读取器，压缩的输入会被自动解压
+    let mut reader = Reader::from_reader(get_reader(input)?);
+
+    // 用于存储 CSV 记录的 Records
+    let mut records = Records {
+        headers: reader.headers()?.iter().map(String::from).collect(),
+        rows: Vec::with_capacity(128),
+    };
 
     // 逐行读取 CSV 数据
     for result in reader.records(){
         let record = result?;
+        records.rows.push(record.iter().map(Value::from).collect());
+    }
+    Ok(records)
+}
+
+impl Records {
+    /// 将记录序列化为指定格式的字符串
+    pub fn serialize(&self, format: OutputFormat, xml: &XmlOpts) -> Result<String> {
+        let content = match format {
+            OutputFormat::Json => serde_json::to_string_pretty(&self.to_values())?,
+            OutputFormat::Yaml => serde_yaml::to_string(&self.to_values())?,
+            OutputFormat::Csv => self.to_csv()?,
+            OutputFormat::Xml => to_xml(self, xml)?,
+        };
+        Ok(content)
+    }
 
+    /// 将记录写入输出文件，根据扩展名决定是否压缩
+    pub fn write(&self, output: &str, format: OutputFormat, xml: &XmlOpts) -> Result<()> {
+        let content = self.serialize(format, xml)?;
+        let mut writer = get_writer(output)?;
+        writer.write_all(content.as_bytes())?;
+        writer.finish()
+    }
+
+    fn to_values(&self) -> Vec<Value> {
         // headers.iter() -> 使用 headers 的迭代器
-        // record.iter() -> 使用 record 的迭代器
-        // zip() 将两个迭代器合并为一个元组的迭代器[(header, record), ..]
+        // row.iter() -> 使用 row 的迭代器
+        // zip() 将两个迭代器合并为一个元组的迭代器[(header, value), ..]
         // collect::<Value> -> 将元组的迭代器转换为 JSON Value
-        let json_value = headers.iter().zip(record.iter()).collect::<serde_json::Value>();
-        ret.push(json_value);
+        self.rows
+            .iter()
+            .map(|row| {
+                self.headers
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect::<serde_json::Map<_, _>>()
+                    .into()
+            })
+            .collect()
     }
 
-    let content = match format {
-        OutputFormat::Json => serde_json::to_string_pretty(&ret)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
-    };
+    fn to_csv(&self) -> Result<String> {
+        let mut writer = Writer::from_writer(Vec::new());
+        writer.write_record(&self.headers)?;
+        for row in &self.rows {
+            writer.write_record(row.iter().map(value_to_field))?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+}
 
-    // 将 JSON 字符串写入输出文件
-    fs::write(output, content)?;
-    Ok(())
+/// 将 JSON 值转换为 CSV 字段，字符串不带引号，null 为空字段
+pub(crate) fn value_to_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_serialize() -> Result<()> {
+        let records = Records {
+            headers: vec!["Name".into(), "Kit Number".into()],
+            rows: vec![vec!["Paulo Dybala".into(), 10.into()], vec!["Mattia Perin".into(), Value::Null]],
+        };
+        assert_eq!(
+            records.serialize(OutputFormat::Csv, &XmlOpts::default())?,
+            "Name,Kit Number\nPaulo Dybala,10\nMattia Perin,\n"
+        );
+        let json: Value = serde_json::from_str(&records.serialize(OutputFormat::Json, &XmlOpts::default())?)?;
+        assert_eq!(json[0]["Kit Number"], 10);
+        Ok(())
+    }
 }
@@ -0,0 +1,120 @@
+use anyhow::Result;
+
+use super::{
+    csv_convert::value_to_field,
+    text::{Blake3, KeyLoader},
+    Records,
+};
+
+/// 遮盖列使用的固定占位符
+const MASK: &str = "***";
+
+/// 假名保留的十六进制字符个数（64 bit）
+const PSEUDONYM_LEN: usize = 16;
+
+/// 假名化使用从密钥派生的子密钥，同一个密钥用于 text sign 时两者的输出无法关联
+const PSEUDONYM_CONTEXT: &str = "rcli csv pseudonymize";
+
+/// 对 CSV 中指定的列进行遮盖或假名化
+#[derive(Default)]
+pub struct ColumnMasker {
+    mask: Vec<String>,
+    pseudonymize: Vec<String>,
+    key: Option<Blake3>,
+}
+
+impl ColumnMasker {
+    /// 创建脱敏器，`key` 为 BLAKE3 密钥文件路径，假名化时必须提供
+    pub fn try_new(mask: Vec<String>, pseudonymize: Vec<String>, key: Option<&str>) -> Result<Self> {
+        let key = key.map(Blake3::load).transpose()?.map(|key| key.derive(PSEUDONYM_CONTEXT));
+        if !pseudonymize.is_empty() && key.is_none() {
+            anyhow::bail!("--pseudonymize requires a BLAKE3 key (--key)");
+        }
+        Ok(Self {
+            mask,
+            pseudonymize,
+            key,
+        })
+    }
+
+    /// 检查需要脱敏的列都存在于表头中，避免拼写错误导致数据泄露
+    pub fn check_headers(&self, headers: &[String]) -> Result<()> {
+        for name in self.mask.iter().chain(&self.pseudonymize) {
+            if !headers.contains(name) {
+                anyhow::bail!("Column not found: {}", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// 对记录中需要脱敏的列进行遮盖或假名化，其余列原样保留
+    pub fn mask_records(&self, records: &mut Records) -> Result<()> {
+        self.check_headers(&records.headers)?;
+        for row in records.rows.iter_mut() {
+            for (header, value) in records.headers.iter().zip(row.iter_mut()) {
+                if self.mask.contains(header) {
+                    *value = MASK.into();
+                } else if self.pseudonymize.contains(header) {
+                    *value = self.pseudonym(&value_to_field(value)).into();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 相同的输入和密钥总是得到相同的假名，因此脱敏后的数据仍可以关联
+    fn pseudonym(&self, value: &str) -> String {
+        let key = self.key.as_ref().expect("key is checked in try_new");
+        let hash = key.keyed_hash(value.as_bytes()).to_hex();
+        hash[..PSEUDONYM_LEN].to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn roster() -> Records {
+        Records {
+            headers: vec!["Name".into(), "DOB".into(), "Position".into()],
+            rows: vec![
+                vec!["Wojciech Szczesny".into(), "Apr 18, 1990".into(), "Goalkeeper".into()],
+                vec!["Mattia Perin".into(), "Nov 10, 1992".into(), "Goalkeeper".into()],
+                vec!["Wojciech Szczesny".into(), "Apr 18, 1990".into(), "Goalkeeper".into()],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_pseudonymize_is_deterministic() -> Result<()> {
+        let masker = ColumnMasker::try_new(vec![], vec!["Name".into()], Some("fixtures/blake3.txt"))?;
+        let mut records = roster();
+        masker.mask_records(&mut records)?;
+
+        let name = records.rows[0][0].as_str().unwrap();
+        assert_eq!(name.len(), PSEUDONYM_LEN);
+        assert_ne!(name, "Wojciech Szczesny");
+        assert_eq!(records.rows[0][0], records.rows[2][0]);
+        assert_ne!(records.rows[0][0], records.rows[1][0]);
+        assert_eq!(records.rows[0][2], Value::from("Goalkeeper"));
+
+        // 与直接用密钥签名的结果不同
+        let signed = Blake3::load("fixtures/blake3.txt")?.keyed_hash(b"Wojciech Szczesny").to_hex();
+        assert_ne!(name, &signed[..PSEUDONYM_LEN]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_and_missing_column() -> Result<()> {
+        let masker = ColumnMasker::try_new(vec!["DOB".into()], vec![], None)?;
+        let mut records = roster();
+        masker.mask_records(&mut records)?;
+        assert_eq!(records.rows[1][1], Value::from(MASK));
+
+        let masker = ColumnMasker::try_new(vec!["Nationality".into()], vec![], None)?;
+        assert!(masker.mask_records(&mut roster()).is_err());
+        assert!(ColumnMasker::try_new(vec![], vec!["Name".into()], None).is_err());
+        Ok(())
+    }
+}
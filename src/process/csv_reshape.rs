@@ -0,0 +1,196 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::cli::{Aggregate, CsvMeltOpts, CsvPivotOpts};
+
+use super::{
+    csv_convert::{read_records, value_to_field},
+    Records, SheetOpts,
+};
+
+/// 生成透视表：`index` 的每个取值一行，`columns` 的每个取值一列，单元格为 `values` 的聚合结果
+pub fn process_pivot(opts: &CsvPivotOpts, output: String) -> Result<()> {
+    let records = read_records(&opts.input, &SheetOpts::default())?;
+    let pivoted = pivot(&records, &opts.index, &opts.columns, opts.values.as_deref(), opts.agg)?;
+    pivoted.write(&output, opts.format, &opts.xml)
+}
+
+/// 逆透视：保留 `id` 列，将 `vars` 列折叠为 变量/值 两列
+pub fn process_melt(opts: &CsvMeltOpts, output: String) -> Result<()> {
+    let records = read_records(&opts.input, &SheetOpts::default())?;
+    let melted = melt(&records, &opts.id, &opts.vars, &opts.var_name, &opts.value_name)?;
+    melted.write(&output, opts.format, &opts.xml)
+}
+
+fn pivot(records: &Records, index: &str, columns: &str, values: Option<&str>, agg: Aggregate) -> Result<Records> {
+    let index_pos = column_position(records, index)?;
+    let columns_pos = column_position(records, columns)?;
+    let values_pos = match values {
+        Some(values) => Some(column_position(records, values)?),
+        None if agg == Aggregate::Count => None,
+        None => anyhow::bail!("--values is required for aggregate {}", agg),
+    };
+
+    // 行和列都按照第一次出现的顺序排列
+    let mut row_keys: Vec<String> = Vec::new();
+    let mut col_keys: Vec<String> = Vec::new();
+    let mut cells: HashMap<(usize, usize), Vec<&Value>> = HashMap::new();
+    for row in &records.rows {
+        let row_key = value_to_field(&row[index_pos]);
+        let col_key = value_to_field(&row[columns_pos]);
+        let r = position_or_insert(&mut row_keys, row_key);
+        let c = position_or_insert(&mut col_keys, col_key);
+        let cell = cells.entry((r, c)).or_default();
+        match values_pos {
+            Some(pos) if !row[pos].is_null() => cell.push(&row[pos]),
+            Some(_) => {}
+            None => cell.push(&row[index_pos]),
+        }
+    }
+
+    let mut headers = vec![index.to_string()];
+    headers.extend(col_keys.iter().cloned());
+    let rows = row_keys
+        .into_iter()
+        .enumerate()
+        .map(|(r, key)| {
+            let mut row = vec![Value::String(key)];
+            for c in 0..col_keys.len() {
+                let cell = cells.get(&(r, c)).map(Vec::as_slice).unwrap_or_default();
+                row.push(aggregate(cell, agg)?);
+            }
+            Ok(row)
+        })
+        .collect::<Result<_>>()?;
+    Ok(Records { headers, rows })
+}
+
+fn melt(records: &Records, id: &[String], vars: &[String], var_name: &str, value_name: &str) -> Result<Records> {
+    let id_pos = id
+        .iter()
+        .map(|name| column_position(records, name))
+        .collect::<Result<Vec<_>>>()?;
+    let vars: Vec<String> = if vars.is_empty() {
+        records.headers.iter().filter(|h| !id.contains(h)).cloned().collect()
+    } else {
+        vars.to_vec()
+    };
+    let var_pos = vars
+        .iter()
+        .map(|name| column_position(records, name))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut headers = id.to_vec();
+    headers.push(var_name.to_string());
+    headers.push(value_name.to_string());
+
+    let mut rows = Vec::with_capacity(records.rows.len() * vars.len());
+    for row in &records.rows {
+        for (name, &pos) in vars.iter().zip(&var_pos) {
+            let mut melted: Vec<Value> = id_pos.iter().map(|&i| row[i].clone()).collect();
+            melted.push(Value::String(name.clone()));
+            melted.push(row[pos].clone());
+            rows.push(melted);
+        }
+    }
+    Ok(Records { headers, rows })
+}
+
+fn aggregate(cell: &[&Value], agg: Aggregate) -> Result<Value> {
+    if agg == Aggregate::Count {
+        return Ok(cell.len().into());
+    }
+    if agg == Aggregate::First {
+        return Ok(cell.first().map(|v| (*v).clone()).unwrap_or_default());
+    }
+    if cell.is_empty() {
+        return Ok(Value::Null);
+    }
+    let nums = cell.iter().map(|v| to_number(v)).collect::<Result<Vec<_>>>()?;
+    let ret = match agg {
+        Aggregate::Sum => nums.iter().sum(),
+        Aggregate::Mean => nums.iter().sum::<f64>() / nums.len() as f64,
+        Aggregate::Min => nums.iter().copied().fold(f64::INFINITY, f64::min),
+        Aggregate::Max => nums.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        Aggregate::Count | Aggregate::First => unreachable!(),
+    };
+    // 整数结果输出为整数，避免出现 "3.0"
+    if ret.fract() == 0.0 && ret.abs() < i64::MAX as f64 {
+        Ok((ret as i64).into())
+    } else {
+        Ok(ret.into())
+    }
+}
+
+fn to_number(value: &Value) -> Result<f64> {
+    match value {
+        Value::Number(n) => n.as_f64().ok_or_else(|| anyhow::anyhow!("Invalid number: {}", n)),
+        Value::String(s) => s
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Not a number: {}", s)),
+        v => anyhow::bail!("Not a number: {}", v),
+    }
+}
+
+fn column_position(records: &Records, name: &str) -> Result<usize> {
+    records
+        .headers
+        .iter()
+        .position(|h| h == name)
+        .ok_or_else(|| anyhow::anyhow!("Column not found: {}", name))
+}
+
+fn position_or_insert(keys: &mut Vec<String>, key: String) -> usize {
+    match keys.iter().position(|k| *k == key) {
+        Some(pos) => pos,
+        None => {
+            keys.push(key);
+            keys.len() - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roster() -> Result<Records> {
+        read_records("assets/juventus.csv", &SheetOpts::default())
+    }
+
+    #[test]
+    fn test_pivot_count_and_sum() -> Result<()> {
+        let records = roster()?;
+        let counts = pivot(&records, "Position", "Nationality", Some("Name"), Aggregate::Count)?;
+        assert_eq!(counts.headers[0], "Position");
+        assert_eq!(counts.headers[1], "Poland");
+        assert_eq!(counts.rows[0][0], Value::from("Goalkeeper"));
+        let total: u64 = counts.rows.iter().flat_map(|r| r[1..].iter()).filter_map(Value::as_u64).sum();
+        assert_eq!(total as usize, records.rows.len());
+
+        let kits = pivot(&records, "Position", "Position", Some("Kit Number"), Aggregate::Max)?;
+        assert_eq!(kits.rows[0][1], Value::from(77));
+        assert!(pivot(&records, "Position", "Nationality", None, Aggregate::Sum).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_melt() -> Result<()> {
+        let records = roster()?;
+        let vars = ["Position".to_string(), "Nationality".to_string()];
+        let melted = melt(&records, &["Name".into()], &vars, "variable", "value")?;
+        assert_eq!(melted.headers, ["Name", "variable", "value"]);
+        assert_eq!(melted.rows.len(), records.rows.len() * 2);
+        assert_eq!(melted.rows[1], vec![
+            Value::from("Wojciech Szczesny"),
+            Value::from("Nationality"),
+            Value::from("Poland"),
+        ]);
+
+        let melted = melt(&records, &["Name".into()], &[], "variable", "value")?;
+        assert_eq!(melted.rows.len(), records.rows.len() * 4);
+        Ok(())
+    }
+}
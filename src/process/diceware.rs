@@ -0,0 +1,174 @@
+use anyhow::Result;
+use rand::{seq::SliceRandom, CryptoRng, Rng};
+use std::fs;
+
+use super::{
+    pattern::{check_unused, class_options},
+    PasswordPolicy, StrengthPolicy,
+};
+
+const EFF_LARGE_WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+const DIGITS: &[u8] = b"0123456789";
+
+pub struct Wordlist {
+    words: Vec<String>,
+}
+
+/// how a passphrase is put together
+#[derive(Debug, Default)]
+pub struct PassphraseOpts {
+    /// number of words
+    pub count: usize,
+    pub separator: String,
+    /// capitalize the first letter of every word
+    pub capitalize: bool,
+    /// number of random digits appended to randomly chosen words
+    pub digits: usize,
+}
+
+/// generate a passphrase, returns it with its entropy in bits;
+/// the character options in `policy` don't apply to words and are rejected
+pub fn process_genpass_words(
+    opts: &PassphraseOpts,
+    wordlist: &Wordlist,
+    policy: &PasswordPolicy,
+    strength: &StrengthPolicy,
+    rng: &mut (impl Rng + CryptoRng),
+) -> Result<(String, f64)> {
+    let options = class_options(policy)
+        .into_iter()
+        .chain([("symbols", policy.symbols.is_some()), ("exclude", !policy.exclude.is_empty())]);
+    check_unused(options, "--words")?;
+    let PassphraseOpts { count, ref separator, capitalize, digits } = *opts;
+    if count == 0 {
+        anyhow::bail!("Passphrase needs at least one word");
+    }
+    let entropy = count as f64 * (wordlist.words.len() as f64).log2() + digits_entropy(count, digits);
+    let max_word_len = wordlist.words.iter().map(|w| w.chars().count()).max().unwrap_or_default();
+    let max_len = count * (max_word_len + separator.chars().count()) + digits;
+    let passphrase = strength.generate(entropy, max_len, || {
+        Ok(generate(wordlist, count, separator, capitalize, digits, rng))
+    })?;
+    Ok((passphrase, entropy))
+}
+
+/// only the digits each word ends up with are visible, not the order they were placed in,
+/// so count C(count + digits - 1, digits) ways to split them over the words, times 10^digits values;
+/// the splits aren't equally likely, so this is still an upper bound
+fn digits_entropy(count: usize, digits: usize) -> f64 {
+    let placements: f64 = (1..=digits).map(|i| ((count - 1 + i) as f64 / i as f64).log2()).sum();
+    placements + digits as f64 * (DIGITS.len() as f64).log2()
+}
+
+fn generate(wordlist: &Wordlist, count: usize, separator: &str, capitalize: bool, digits: usize, rng: &mut impl Rng) -> String {
+    let mut words: Vec<String> = (0..count)
+        .map(|_| wordlist.words.choose(rng).expect("wordlist won't be empty!").clone())
+        .collect();
+
+    if capitalize {
+        for word in words.iter_mut() {
+            *word = capitalize_word(word);
+        }
+    }
+
+    // each digit is appended to a randomly chosen word
+    for _ in 0..digits {
+        let digit = *DIGITS.choose(rng).expect("DIGITS won't be empty!");
+        let i = rng.gen_range(0..count);
+        words[i].push(digit as char);
+    }
+    words.join(separator)
+}
+
+impl Wordlist {
+    /// EFF's long wordlist with 7776 words (~12.9 bits per word)
+    pub fn eff_large() -> Self {
+        Self::parse(EFF_LARGE_WORDLIST).expect("embedded wordlist is valid")
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// one word per line, a leading dice number like `11111<TAB>abacus` is ignored
+    fn parse(content: &str) -> Result<Self> {
+        let mut words: Vec<String> = content
+            .lines()
+            .filter_map(|line| line.split_whitespace().last())
+            .map(String::from)
+            .collect();
+        words.sort();
+        words.dedup();
+        if words.len() < 2 {
+            anyhow::bail!("Wordlist needs at least 2 distinct words, got {}", words.len());
+        }
+        Ok(Self { words })
+    }
+}
+
+fn capitalize_word(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::genpass_rng;
+
+    fn passphrase_opts(count: usize, separator: &str, capitalize: bool, digits: usize) -> PassphraseOpts {
+        PassphraseOpts { count, separator: separator.into(), capitalize, digits }
+    }
+
+    #[test]
+    fn test_eff_wordlist() {
+        let wordlist = Wordlist::eff_large();
+        assert_eq!(wordlist.words.len(), 7776);
+        assert_eq!(wordlist.words[0], "abacus");
+    }
+
+    #[test]
+    fn test_process_genpass_words() -> Result<()> {
+        // EFF words may contain '-', so split on a space instead
+        let (passphrase, entropy) = process_genpass_words(&passphrase_opts(6, " ", true, 0), &Wordlist::eff_large(), &PasswordPolicy::default(), &StrengthPolicy::default(), &mut genpass_rng(None))?;
+        let words: Vec<&str> = passphrase.split(' ').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|w| w.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert!((entropy - 6.0 * 7776f64.log2()).abs() < 1e-9);
+
+        let (passphrase, _) = process_genpass_words(&passphrase_opts(4, " ", false, 2), &Wordlist::eff_large(), &PasswordPolicy::default(), &StrengthPolicy::default(), &mut genpass_rng(None))?;
+        assert_eq!(passphrase.chars().filter(char::is_ascii_digit).count(), 2);
+        assert!(process_genpass_words(&passphrase_opts(0, "-", false, 0), &Wordlist::eff_large(), &PasswordPolicy::default(), &StrengthPolicy::default(), &mut genpass_rng(None)).is_err());
+
+        // 2 digits over 4 words: 10 ways to split them (not 16 orderings), 100 values
+        let (_, entropy) = process_genpass_words(&passphrase_opts(4, " ", false, 2), &Wordlist::eff_large(), &PasswordPolicy::default(), &StrengthPolicy::default(), &mut genpass_rng(None))?;
+        assert!((entropy - (4.0 * 7776f64.log2() + 1000f64.log2())).abs() < 1e-9);
+        // a single word can only take every digit itself
+        assert!((digits_entropy(1, 3) - 1000f64.log2()).abs() < 1e-9);
+
+        // character options don't apply to words
+        let policy = PasswordPolicy {
+            charset: Some("abc".into()),
+            min_upper: Some(3),
+            exclude: "e".into(),
+            ..Default::default()
+        };
+        let err = process_genpass_words(&passphrase_opts(4, "-", false, 0), &Wordlist::eff_large(), &policy, &StrengthPolicy::default(), &mut genpass_rng(None)).unwrap_err();
+        assert_eq!(err.to_string(), "charset, min-upper, exclude can't be used with --words");
+        Ok(())
+    }
+
+    #[test]
+    fn test_seeded_words_are_reproducible() -> Result<()> {
+        let generate = |seed| {
+            process_genpass_words(&passphrase_opts(5, "-", true, 2), &Wordlist::eff_large(), &PasswordPolicy::default(), &StrengthPolicy::default(), &mut genpass_rng(Some(seed)))
+        };
+        assert_eq!(generate(42)?, generate(42)?);
+        assert_ne!(generate(42)?.0, generate(43)?.0);
+        Ok(())
+    }
+}
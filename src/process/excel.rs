@@ -0,0 +1,146 @@
+use anyhow::Result;
+use calamine::{open_workbook_auto, Data, Reader};
+use chrono::{NaiveDateTime, Timelike};
+use serde_json::Value;
+use std::{fmt::Write, path::Path};
+
+use super::{csv_convert::value_to_field, Records};
+
+const SPREADSHEET_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+/// 读取表格文件时的选项
+#[derive(Debug, Default)]
+pub struct SheetOpts {
+    /// 工作表名称或从 0 开始的索引，默认读取第一个工作表
+    pub sheet: Option<String>,
+    /// 表头所在行（从 0 开始），之前的行会被忽略
+    pub header_row: usize,
+    /// 日期单元格的 strftime 格式，默认输出 ISO 8601
+    pub date_format: Option<String>,
+}
+
+/// 根据扩展名判断输入是否为表格文件
+pub(crate) fn is_spreadsheet(input: &str) -> bool {
+    Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SPREADSHEET_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// 读取工作表，保留数字、布尔值等单元格类型
+pub(crate) fn read_sheet(input: &str, opts: &SheetOpts) -> Result<Records> {
+    // 格式非法或包含时区（NaiveDateTime 没有时区）时 chrono 会在 to_string 中 panic，先格式化一个样例检查
+    if let Some(fmt) = &opts.date_format {
+        if write!(String::new(), "{}", NaiveDateTime::default().format(fmt)).is_err() {
+            anyhow::bail!("Invalid date format: {}", fmt);
+        }
+    }
+    let mut workbook = open_workbook_auto(input)?;
+    let names = workbook.sheet_names();
+    let name = match &opts.sheet {
+        None => names.first(),
+        Some(sheet) if names.contains(sheet) => Some(sheet),
+        Some(sheet) => sheet.parse::<usize>().ok().and_then(|i| names.get(i)),
+    };
+    let name = match name {
+        Some(name) => name.clone(),
+        None => anyhow::bail!(
+            "Sheet not found: {}, available sheets: {}",
+            opts.sheet.as_deref().unwrap_or("0"),
+            names.join(", ")
+        ),
+    };
+    let range = workbook.worksheet_range(&name)?;
+
+    // range 从第一个非空单元格开始，需要换算成相对的行偏移
+    let start = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+    let mut rows = range.rows().skip(opts.header_row.saturating_sub(start));
+    let headers = match rows.next() {
+        Some(row) => row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| match cell_to_value(cell, opts) {
+                Value::Null => format!("column{}", i + 1),
+                v => value_to_field(&v),
+            })
+            .collect(),
+        None => anyhow::bail!("Header row {} is out of range in sheet {}", opts.header_row, name),
+    };
+
+    let rows = rows
+        .filter(|row| row.iter().any(|cell| *cell != Data::Empty))
+        .map(|row| row.iter().map(|cell| cell_to_value(cell, opts)).collect())
+        .collect();
+    Ok(Records { headers, rows })
+}
+
+fn cell_to_value(cell: &Data, opts: &SheetOpts) -> Value {
+    match cell {
+        Data::Empty => Value::Null,
+        Data::Int(v) => (*v).into(),
+        // Excel 中的数字都以浮点数保存，整数值还原为整数
+        Data::Float(v) if v.fract() == 0.0 && v.abs() < i64::MAX as f64 => (*v as i64).into(),
+        Data::Float(v) => (*v).into(),
+        Data::Bool(v) => (*v).into(),
+        Data::String(v) | Data::DateTimeIso(v) | Data::DurationIso(v) => v.as_str().into(),
+        Data::DateTime(v) => match v.as_datetime() {
+            Some(dt) if !v.is_duration() => format_datetime(dt, opts).into(),
+            _ => v.as_f64().into(),
+        },
+        Data::Error(e) => e.to_string().into(),
+    }
+}
+
+fn format_datetime(dt: NaiveDateTime, opts: &SheetOpts) -> String {
+    match &opts.date_format {
+        Some(fmt) => dt.format(fmt).to_string(),
+        None if dt.num_seconds_from_midnight() == 0 => dt.format("%Y-%m-%d").to_string(),
+        None => dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_sheet() -> Result<()> {
+        let opts = SheetOpts {
+            sheet: Some("Players".into()),
+            header_row: 2,
+            date_format: None,
+        };
+        let records = read_sheet("fixtures/roster.xlsx", &opts)?;
+        assert_eq!(records.headers, ["Name", "Position", "DOB", "Kit Number", "Captain"]);
+        assert_eq!(records.rows.len(), 3);
+        assert_eq!(records.rows[0][2], Value::from("1990-04-18"));
+        assert_eq!(records.rows[0][3], Value::from(1));
+        assert_eq!(records.rows[1][4], Value::from(true));
+
+        // 通过索引选择工作表
+        let opts = SheetOpts { sheet: Some("1".into()), ..opts };
+        assert_eq!(read_sheet("fixtures/roster.xlsx", &opts)?.rows.len(), 3);
+
+        let opts = SheetOpts { sheet: Some("Staff".into()), ..opts };
+        assert!(read_sheet("fixtures/roster.xlsx", &opts).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_format() -> Result<()> {
+        let opts = SheetOpts {
+            sheet: Some("Players".into()),
+            header_row: 2,
+            date_format: Some("%d/%m/%Y".into()),
+        };
+        assert_eq!(read_sheet("fixtures/roster.xlsx", &opts)?.rows[0][2], Value::from("18/04/1990"));
+
+        // 非法格式返回错误而不是 panic
+        let opts = SheetOpts { date_format: Some("%Q".into()), ..opts };
+        assert!(read_sheet("fixtures/roster.xlsx", &opts).is_err());
+        // 日期没有时区，%z 无法格式化
+        let opts = SheetOpts { date_format: Some("%Y-%m-%d %z".into()), ..opts };
+        assert!(read_sheet("fixtures/roster.xlsx", &opts).is_err());
+        Ok(())
+    }
+}
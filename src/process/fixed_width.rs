@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Number, Value};
+use std::{fs, io::{BufRead, BufReader}, path::Path};
+
+use crate::{
+    cli::{OutputFormat, XmlOpts},
+    get_reader,
+};
+
+use super::Records;
+
+/// 定长记录的布局，按顺序声明每个字段
+#[derive(Debug, Deserialize)]
+pub struct Layout {
+    fields: Vec<FieldSpec>,
+}
+
+/// 单个字段：名称、起始偏移（从 0 开始的字符数）、宽度和类型
+#[derive(Debug, Deserialize)]
+struct FieldSpec {
+    name: String,
+    offset: usize,
+    width: usize,
+    #[serde(default, rename = "type")]
+    kind: FieldType,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FieldType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Bool,
+}
+
+/// 按照布局文件解析定长文本，并转换为指定格式
+pub fn process_fixed(input: &str, layout: &str, output: String, format: OutputFormat, xml: &XmlOpts) -> Result<()> {
+    let layout = Layout::load(layout)?;
+    let reader = BufReader::new(get_reader(input)?);
+    layout.parse(reader)?.write(&output, format, xml)
+}
+
+impl Layout {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let layout: Layout = serde_yaml::from_str(&content)?;
+        if layout.fields.is_empty() {
+            anyhow::bail!("Layout must declare at least one field");
+        }
+        Ok(layout)
+    }
+
+    /// 逐行解析记录，空行会被跳过
+    fn parse(&self, reader: impl BufRead) -> Result<Records> {
+        let mut records = Records {
+            headers: self.fields.iter().map(|f| f.name.clone()).collect(),
+            rows: Vec::with_capacity(128),
+        };
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let chars: Vec<char> = line.chars().collect();
+            let row = self
+                .fields
+                .iter()
+                .map(|field| {
+                    field
+                        .extract(&chars)
+                        .with_context(|| format!("line {}, field {}", i + 1, field.name))
+                })
+                .collect::<Result<_>>()?;
+            records.rows.push(row);
+        }
+        Ok(records)
+    }
+}
+
+impl FieldSpec {
+    /// 截取字段并按类型转换，超出行尾的部分视为空，空的非字符串字段为 null
+    fn extract(&self, chars: &[char]) -> Result<Value> {
+        let start = self.offset.min(chars.len());
+        let end = (self.offset + self.width).min(chars.len());
+        let raw: String = chars[start..end].iter().collect();
+        let raw = raw.trim();
+
+        let value = match self.kind {
+            FieldType::String => Value::String(raw.to_string()),
+            _ if raw.is_empty() => Value::Null,
+            FieldType::Integer => raw.parse::<i64>()?.into(),
+            FieldType::Float => {
+                let v = raw.parse::<f64>()?;
+                Number::from_f64(v)
+                    .map(Value::Number)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid float: {}", raw))?
+            }
+            FieldType::Bool => match raw.to_ascii_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "1" => true.into(),
+                "false" | "f" | "no" | "n" | "0" => false.into(),
+                v => anyhow::bail!("Invalid bool: {}", v),
+            },
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixed_width() -> Result<()> {
+        let layout = Layout::load("fixtures/layout.yaml")?;
+        let reader = BufReader::new(get_reader("fixtures/players.dat")?);
+        let records = layout.parse(reader)?;
+
+        assert_eq!(records.headers, ["Name", "Position", "Kit Number", "Height", "Captain"]);
+        assert_eq!(records.rows.len(), 3);
+        assert_eq!(records.rows[0], vec![
+            Value::from("Wojciech Szczesny"),
+            Value::from("Goalkeeper"),
+            Value::from(1),
+            Value::from(1.95),
+            Value::from(false),
+        ]);
+        assert_eq!(records.rows[2][2], Value::Null);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_field() {
+        let layout = Layout::load("fixtures/layout.yaml").unwrap();
+        let line = format!("{:<20}{:<12}{:>3}", "Paulo Dybala", "Forward", "X");
+        assert!(layout.parse(line.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use rand::{seq::SliceRandom, CryptoRng, Rng};
+
+use crate::cli::{GenIdOpts, IdKind};
+
+/// Crockford base32 alphabet used by ULID
+const CROCKFORD: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// ULID and UUIDv7 timestamps are 48 bits of milliseconds
+const MAX_TIMESTAMP: u64 = (1 << 48) - 1;
+
+/// random bits after the timestamp: 12 + 62 around the version and variant in a UUIDv7, 80 in a ULID
+const UUID7_RANDOM_BITS: u32 = 74;
+const ULID_RANDOM_BITS: u32 = 80;
+
+/// generate `opts.count` ids, drawing all randomness from `rng`
+pub fn process_gen_id(opts: &GenIdOpts, rng: &mut (impl Rng + CryptoRng)) -> Result<Vec<String>> {
+    let timestamp = match (opts.kind, opts.timestamp) {
+        (IdKind::Uuid7 | IdKind::Ulid, Some(timestamp)) if timestamp > MAX_TIMESTAMP => {
+            anyhow::bail!("Timestamp {} doesn't fit in 48 bits", timestamp)
+        }
+        (IdKind::Uuid7 | IdKind::Ulid, Some(timestamp)) => timestamp,
+        (IdKind::Uuid7 | IdKind::Ulid, None) => SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
+        (kind, Some(_)) => anyhow::bail!("--timestamp only applies to uuid7 and ulid, not {}", kind),
+        (_, None) => 0,
+    };
+
+    let alphabet: Vec<char> = opts.alphabet.chars().collect();
+    if opts.kind == IdKind::Nanoid {
+        let mut unique = alphabet.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        if unique.len() < 2 || unique.len() != alphabet.len() {
+            anyhow::bail!("Nanoid alphabet needs at least 2 distinct characters and no duplicates");
+        }
+        if opts.length == 0 {
+            anyhow::bail!("Nanoid length must be at least 1");
+        }
+    }
+
+    // every id in a batch shares the millisecond, so after the first one the random part counts up
+    // (RFC 9562 section 6.2 method 2, ULID monotonicity) to keep the batch sorted
+    let mut random = None;
+    let mut ids = Vec::with_capacity(opts.count);
+    for _ in 0..opts.count {
+        let id = match opts.kind {
+            IdKind::Uuid4 => uuid4(rng),
+            IdKind::Uuid7 => uuid7(timestamp, next_random(&mut random, UUID7_RANDOM_BITS, rng)?),
+            IdKind::Ulid => ulid(timestamp, next_random(&mut random, ULID_RANDOM_BITS, rng)?),
+            IdKind::Nanoid => nanoid(&alphabet, opts.length, rng),
+        };
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// fresh random `bits` for the first id, the previous value plus one after that
+fn next_random(random: &mut Option<u128>, bits: u32, rng: &mut impl Rng) -> Result<u128> {
+    let max = (1u128 << bits) - 1;
+    let next = match *random {
+        None => rng.gen::<u128>() & max,
+        Some(prev) if prev < max => prev + 1,
+        Some(_) => anyhow::bail!("Too many ids in one millisecond, the random part would overflow"),
+    };
+    *random = Some(next);
+    Ok(next)
+}
+
+/// RFC 9562 version 4: 122 random bits
+fn uuid4(rng: &mut impl Rng) -> String {
+    let mut bytes: [u8; 16] = rng.gen();
+    set_version(&mut bytes, 4);
+    format_uuid(&bytes)
+}
+
+/// RFC 9562 version 7: 48-bit big-endian millisecond timestamp followed by 74 random bits,
+/// the top 12 of them before the variant and the low 62 after it
+fn uuid7(timestamp: u64, random: u128) -> String {
+    let value = ((timestamp as u128) << 80) | ((random >> 62) << 64) | (random & ((1 << 62) - 1));
+    let mut bytes = value.to_be_bytes();
+    set_version(&mut bytes, 7);
+    format_uuid(&bytes)
+}
+
+fn set_version(bytes: &mut [u8; 16], version: u8) {
+    bytes[6] = (bytes[6] & 0x0f) | (version << 4);
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// 48-bit millisecond timestamp and 80 random bits as 26 Crockford base32 characters
+fn ulid(timestamp: u64, random: u128) -> String {
+    let value = ((timestamp as u128) << 80) | random;
+    (0..26)
+        .rev()
+        .map(|i| CROCKFORD[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+fn nanoid(alphabet: &[char], length: usize, rng: &mut impl Rng) -> String {
+    (0..length)
+        .map(|_| *alphabet.choose(rng).expect("alphabet is checked to be non-empty"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::genpass_rng;
+    use clap::Parser;
+
+    fn opts(args: &[&str]) -> GenIdOpts {
+        GenIdOpts::parse_from(["id"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_uuid() -> Result<()> {
+        let ids = process_gen_id(&opts(&["--count", "3"]), &mut genpass_rng(None))?;
+        assert_eq!(ids.len(), 3);
+        for id in &ids {
+            assert_eq!(id.len(), 36);
+            assert_eq!(&id[14..15], "4");
+            assert!("89ab".contains(&id[19..20]));
+        }
+
+        let opts = opts(&["--kind", "uuid7", "--timestamp", "1700000000000", "--seed", "1"]);
+        let ids = process_gen_id(&opts, &mut genpass_rng(opts.seed))?;
+        assert_eq!(ids, process_gen_id(&opts, &mut genpass_rng(opts.seed))?);
+        assert!(ids[0].starts_with("018bcfe5-6800-7"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ulid_and_nanoid() -> Result<()> {
+        // the ULID spec example timestamp 1469922850259 encodes as 01ARZ3NDEK
+        let ids = process_gen_id(&opts(&["--kind", "ulid", "--timestamp", "1469922850259"]), &mut genpass_rng(None))?;
+        assert_eq!(ids[0].len(), 26);
+        assert!(ids[0].starts_with("01ARZ3NDEK"));
+
+        let ids = process_gen_id(&opts(&["--kind", "nanoid", "--alphabet", "abc", "-l", "8"]), &mut genpass_rng(None))?;
+        assert_eq!(ids[0].len(), 8);
+        assert!(ids[0].chars().all(|c| "abc".contains(c)));
+
+        assert!(process_gen_id(&opts(&["--kind", "nanoid", "--alphabet", "aa"]), &mut genpass_rng(None)).is_err());
+        assert!(process_gen_id(&opts(&["--kind", "uuid4", "--timestamp", "1"]), &mut genpass_rng(None)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_monotonic_within_a_millisecond() -> Result<()> {
+        for kind in ["uuid7", "ulid"] {
+            let ids = process_gen_id(&opts(&["--kind", kind, "--count", "1000"]), &mut genpass_rng(None))?;
+            assert!(ids.windows(2).all(|pair| pair[0] < pair[1]), "{}", kind);
+        }
+        let ids = process_gen_id(&opts(&["--kind", "uuid7", "--count", "2"]), &mut genpass_rng(None))?;
+        assert!(ids.iter().all(|id| &id[14..15] == "7" && "89ab".contains(&id[19..20])));
+
+        let mut random = Some((1u128 << ULID_RANDOM_BITS) - 1);
+        assert!(next_random(&mut random, ULID_RANDOM_BITS, &mut genpass_rng(None)).is_err());
+        Ok(())
+    }
+}
@@ -1,48 +1,398 @@
-use anyhow::Ok;  
-use rand::seq::SliceRandom;
+use std::{fs, path::Path};
 
-const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
-const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
-const NUMBER: &[u8] = b"123456789";
-const SYMBOL: &[u8] = b"!@#$%^&*_";
+use anyhow::Ok;
+use rand::{seq::SliceRandom, CryptoRng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::Deserialize;
 
+use crate::cli::PolicyOpts;
 
-pub fn process_genpass(length: u8, upper: bool, lower: bool, number: bool, symbol: bool) -> anyhow::Result<String> {
-    let mut rng = rand::thread_rng();
-    let mut password = Vec::new();
-    let mut chars = Vec::new();
+use super::{charset_entropy, StrengthPolicy};
+
+const UPPER: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijkmnopqrstuvwxyz";
+pub(super) const NUMBER: &str = "123456789";
+pub(super) const SYMBOL: &str = "!@#$%^&*_";
+
+/// easily confused characters left out of the default sets unless `include_ambiguous` is set
+const AMBIGUOUS_UPPER: &str = "IO";
+const AMBIGUOUS_LOWER: &str = "l";
+const AMBIGUOUS_NUMBER: &str = "0";
+
+/// which characters a password may contain and how many of each class it needs,
+/// can be loaded from a YAML policy file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    /// reject lengths below this, e.g. when `--length` overrides a policy file
+    pub min_length: Option<usize>,
+    /// reject lengths above this
+    pub max_length: Option<usize>,
+    pub upper: bool,
+    pub lower: bool,
+    pub number: bool,
+    pub symbol: bool,
+    /// replaces the default symbol set, e.g. "!#$"
+    pub symbols: Option<String>,
+    /// draw from exactly these characters instead of the built-in sets,
+    /// they are still grouped into classes for the minimum counts
+    pub charset: Option<String>,
+    /// characters never used
+    pub exclude: String,
+    pub include_ambiguous: bool,
+    /// minimum count per class, defaults to one for every enabled class
+    pub min_upper: Option<usize>,
+    pub min_lower: Option<usize>,
+    pub min_number: Option<usize>,
+    pub min_symbol: Option<usize>,
+}
+
+/// the characters of one class and how many of them a password needs
+#[derive(Debug)]
+struct CharClass {
+    chars: Vec<char>,
+    min: usize,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            min_length: None,
+            max_length: None,
+            upper: true,
+            lower: true,
+            number: true,
+            symbol: true,
+            symbols: None,
+            charset: None,
+            exclude: String::new(),
+            include_ambiguous: false,
+            min_upper: None,
+            min_lower: None,
+            min_number: None,
+            min_symbol: None,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let policy: PasswordPolicy = serde_yaml::from_str(&content)?;
+        Ok(policy)
+    }
 
-    if upper {
-        chars.extend_from_slice(UPPER);
-        password.push(*UPPER.choose(&mut rng).expect("UPPER won't be empty!"))
+    /// the length must fit the policy bounds and leave room for every class minimum
+    fn check_length(&self, classes: &[CharClass]) -> anyhow::Result<()> {
+        if self.length == 0 {
+            anyhow::bail!("Password length must be at least 1");
+        }
+        self.check_length_bounds(self.length)?;
+        let required: usize = classes.iter().map(|class| class.min).sum();
+        if self.length < required {
+            anyhow::bail!(
+                "Password length {} is too short: the enabled character classes require at least {} characters",
+                self.length,
+                required
+            );
+        }
+        Ok(())
     }
-    
-    if lower{
-        chars.extend_from_slice(LOWER);
-        password.push(*LOWER.choose(&mut rng).expect("LOWER won't be empty!"))
 
+    /// `length` must be within the policy file's `min_length` and `max_length`
+    pub(super) fn check_length_bounds(&self, length: usize) -> anyhow::Result<()> {
+        if let Some(min_length) = self.min_length.filter(|min| length < *min) {
+            anyhow::bail!("Password length {} is below the policy minimum of {}", length, min_length);
+        }
+        if let Some(max_length) = self.max_length.filter(|max| length > *max) {
+            anyhow::bail!("Password length {} exceeds the policy maximum of {}", length, max_length);
+        }
+        Ok(())
     }
 
-    if number {
-        chars.extend_from_slice(NUMBER);
-        password.push(*NUMBER.choose(&mut rng).expect("NUMBER won't be empty!"))
+    /// resolve the enabled classes after applying the charset, exclusions and ambiguity rules
+    fn classes(&self) -> anyhow::Result<Vec<CharClass>> {
+        let classes = [
+            ("upper", self.upper, UPPER, AMBIGUOUS_UPPER, self.min_upper, char::is_uppercase as fn(char) -> bool),
+            ("lower", self.lower, LOWER, AMBIGUOUS_LOWER, self.min_lower, char::is_lowercase),
+            ("number", self.number, NUMBER, AMBIGUOUS_NUMBER, self.min_number, char::is_numeric),
+            ("symbol", self.symbol, self.symbols.as_deref().unwrap_or(SYMBOL), "", self.min_symbol, |c: char| !c.is_alphanumeric()),
+        ];
+
+        if classes.iter().all(|(_, enabled, ..)| !enabled) {
+            anyhow::bail!("All character classes are disabled, enable at least one of upper, lower, number or symbol");
+        }
+
+        let mut ret = Vec::new();
+        for (name, enabled, default, ambiguous, min, belongs) in classes {
+            if !enabled {
+                if min.is_some_and(|min| min > 0) {
+                    anyhow::bail!("A minimum count is set for {} characters but the class is disabled", name);
+                }
+                continue;
+            }
+            let mut chars: Vec<char> = match &self.charset {
+                Some(charset) => charset.chars().filter(|c| belongs(*c)).collect(),
+                None if self.include_ambiguous => default.chars().chain(ambiguous.chars()).collect(),
+                None => default.chars().collect(),
+            };
+            chars.retain(|c| !self.exclude.contains(*c));
+            chars.sort_unstable();
+            chars.dedup();
+
+            let min = min.unwrap_or(if chars.is_empty() { 0 } else { 1 });
+            if chars.is_empty() {
+                if min > 0 {
+                    anyhow::bail!("No {} characters left for a minimum count of {}", name, min);
+                }
+                continue;
+            }
+            ret.push(CharClass { chars, min });
+        }
 
+        if ret.is_empty() {
+            anyhow::bail!("No characters left to generate a password from");
+        }
+        Ok(ret)
     }
+}
 
-    if symbol {
-        chars.extend_from_slice(SYMBOL);
-        password.push(*SYMBOL.choose(&mut rng).expect("SYMBOL won't be empty!"))
+impl TryFrom<&PolicyOpts> for PasswordPolicy {
+    type Error = anyhow::Error;
 
+    /// a policy file is the base, otherwise the flags are used; `--length` overrides both
+    fn try_from(opts: &PolicyOpts) -> anyhow::Result<Self> {
+        let mut policy = match &opts.policy {
+            Some(path) => PasswordPolicy::load(path)?,
+            None => PasswordPolicy {
+                upper: opts.uppercase,
+                lower: opts.lowercase,
+                number: opts.number,
+                symbol: opts.symbol,
+                symbols: opts.symbols.clone(),
+                charset: opts.charset.clone(),
+                exclude: opts.exclude.clone(),
+                include_ambiguous: opts.include_ambiguous,
+                min_upper: opts.min_upper,
+                min_lower: opts.min_lower,
+                min_number: opts.min_number,
+                min_symbol: opts.min_symbol,
+                ..Default::default()
+            },
+        };
+        if let Some(length) = opts.length {
+            policy.length = length;
+        }
+        Ok(policy)
     }
+}
 
-    for _ in 0..(length - password.len() as u8) {
-        let c = chars.choose(&mut rng).expect("char won't be empty in context");
+pub fn process_genpass(policy: &PasswordPolicy, strength: &StrengthPolicy) -> anyhow::Result<String> {
+    process_genpass_with_rng(policy, strength, &mut rand::thread_rng())
+}
+
+/// same as `process_genpass`, drawing every character from the caller's `rng`
+pub fn process_genpass_with_rng(
+    policy: &PasswordPolicy,
+    strength: &StrengthPolicy,
+    rng: &mut (impl Rng + CryptoRng),
+) -> anyhow::Result<String> {
+    let classes = policy.classes()?;
+    policy.check_length(&classes)?;
+    let entropy = classes_entropy(policy.length, &classes);
+    strength.generate(entropy, policy.length, || Ok(generate(policy.length, &classes, rng)))
+}
+
+/// CSPRNG for the generators, seeded from the OS unless a `seed` is given;
+/// a seeded generator is predictable and must only be used for reproducible fixtures
+pub fn genpass_rng(seed: Option<u64>) -> ChaCha20Rng {
+    match seed {
+        Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+        None => ChaCha20Rng::from_entropy(),
+    }
+}
+
+/// approximate entropy in bits of a password generated with this policy
+pub fn genpass_entropy(policy: &PasswordPolicy) -> anyhow::Result<f64> {
+    Ok(classes_entropy(policy.length, &policy.classes()?))
+}
+
+fn classes_entropy(length: usize, classes: &[CharClass]) -> f64 {
+    let charset_len = classes.iter().map(|class| class.chars.len()).sum();
+    charset_entropy(length, charset_len)
+}
+
+fn generate(length: usize, classes: &[CharClass], rng: &mut impl Rng) -> String {
+    let mut password = Vec::with_capacity(length);
+    let mut chars = Vec::new();
+
+    for class in classes {
+        chars.extend_from_slice(&class.chars);
+        for _ in 0..class.min {
+            password.push(*class.chars.choose(rng).expect("classes are never empty"));
+        }
+    }
+
+    for _ in 0..length - password.len() {
+        let c = chars.choose(rng).expect("char won't be empty in context");
         password.push(*c);
     }
 
-    password.shuffle(&mut rng);
+    password.shuffle(rng);
+
+    password.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(password: &str, chars: &str) -> usize {
+        password.chars().filter(|c| chars.contains(*c)).count()
+    }
+
+    #[test]
+    fn test_class_minimums_and_exclusions() -> anyhow::Result<()> {
+        let policy = PasswordPolicy {
+            length: 20,
+            min_upper: Some(2),
+            min_symbol: Some(3),
+            exclude: "ABC#".into(),
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let password = process_genpass(&policy, &StrengthPolicy::default())?;
+            assert_eq!(password.len(), 20);
+            assert!(count(&password, "DEFGHJKLMNPQRSTUVWXYZ") >= 2);
+            assert!(count(&password, "!@$%^&*_") >= 3);
+            assert_eq!(count(&password, "ABC#IOl0"), 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_charset_and_ambiguous() -> anyhow::Result<()> {
+        let policy = PasswordPolicy {
+            charset: Some("ab01".into()),
+            ..Default::default()
+        };
+        let password = process_genpass(&policy, &StrengthPolicy::default())?;
+        assert_eq!(count(&password, "ab01"), 16);
+        assert_eq!(genpass_entropy(&policy)?, 32.0);
 
-    let password = String::from_utf8(password)?;
+        let policy = PasswordPolicy {
+            include_ambiguous: true,
+            ..Default::default()
+        };
+        let classes = policy.classes()?;
+        assert!(classes[0].chars.contains(&'O'));
+        assert!(classes[2].chars.contains(&'0'));
+
+        let policy = PasswordPolicy {
+            charset: Some("abc".into()),
+            min_number: Some(1),
+            ..Default::default()
+        };
+        assert!(policy.classes().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_file() -> anyhow::Result<()> {
+        let mut policy = PasswordPolicy::load("fixtures/password_policy.yaml")?;
+        let password = process_genpass(&policy, &StrengthPolicy::default())?;
+        assert_eq!(password.len(), 12);
+        assert_eq!(count(&password, "@%^&*_"), 0);
+        assert!(count(&password, "!#$") >= 2);
+
+        policy.length = 24;
+        assert!(process_genpass(&policy, &StrengthPolicy::default()).is_err());
+
+        // only --length may override the file, class flags would be ignored
+        use clap::Parser;
+        let parse = |args: &[&str]| crate::cli::GenPassOpts::try_parse_from(["genpass", "--policy", "fixtures/password_policy.yaml"].iter().chain(args));
+        assert!(parse(&["--length", "14"]).is_ok());
+        assert!(parse(&["--symbol", "false"]).is_err());
+        assert!(parse(&["--uppercase", "true"]).is_err());
+        Ok(())
+    }
 
-    Ok(password)
-}
\ No newline at end of file
+    #[test]
+    fn test_length_edge_cases() -> anyhow::Result<()> {
+        let strength = StrengthPolicy::default();
+
+        // shorter than the four default classes
+        let policy = PasswordPolicy {
+            length: 3,
+            ..Default::default()
+        };
+        assert!(process_genpass(&policy, &strength).is_err());
+
+        let policy = PasswordPolicy {
+            length: 4,
+            ..Default::default()
+        };
+        assert_eq!(process_genpass(&policy, &strength)?.len(), 4);
+
+        let policy = PasswordPolicy {
+            length: 5,
+            min_symbol: Some(3),
+            ..Default::default()
+        };
+        assert!(process_genpass(&policy, &strength).is_err());
+
+        let policy = PasswordPolicy {
+            length: 0,
+            ..Default::default()
+        };
+        assert!(process_genpass(&policy, &strength).is_err());
+
+        // a single class needs a single character
+        let policy = PasswordPolicy {
+            length: 1,
+            upper: false,
+            lower: false,
+            symbol: false,
+            ..Default::default()
+        };
+        assert_eq!(count(&process_genpass(&policy, &strength)?, NUMBER), 1);
+
+        let policy = PasswordPolicy {
+            upper: false,
+            lower: false,
+            number: false,
+            symbol: false,
+            ..Default::default()
+        };
+        let err = process_genpass(&policy, &strength).unwrap_err();
+        assert!(err.to_string().contains("All character classes are disabled"));
+
+        let policy = PasswordPolicy {
+            length: 1024,
+            ..Default::default()
+        };
+        assert_eq!(process_genpass(&policy, &strength)?.len(), 1024);
+        Ok(())
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() -> anyhow::Result<()> {
+        let strength = StrengthPolicy::default();
+        let mut rng = genpass_rng(Some(42));
+        let first = process_genpass_with_rng(&PasswordPolicy::default(), &strength, &mut rng)?;
+        let second = process_genpass_with_rng(&PasswordPolicy::default(), &strength, &mut rng)?;
+        assert_eq!(first, "EZz3^3M1WbgH7^kv");
+        assert_eq!(second, "@mrdF&Fpi^So8EUN");
+
+        let policy = PasswordPolicy {
+            length: 12,
+            charset: Some("abc123".into()),
+            ..Default::default()
+        };
+        let password = process_genpass_with_rng(&policy, &strength, &mut genpass_rng(Some(7)))?;
+        assert_eq!(password, "2321cbac311b");
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use scrypt::Scrypt;
+
+use crate::{
+    cli::{HashAlgorithm, HashPasswordOpts},
+    read_secret,
+};
+
+/// bcrypt silently ignores everything after the first 72 bytes
+const BCRYPT_MAX_LEN: usize = 72;
+
+/// hash `password` with a random salt and the parameters from `opts`
+pub fn process_hash_password(password: &str, opts: &HashPasswordOpts) -> Result<String> {
+    if password.is_empty() {
+        anyhow::bail!("Password must not be empty");
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = match opts.algo {
+        HashAlgorithm::Argon2id => {
+            let params = Params::new(opts.memory, opts.iterations, opts.parallelism, None)
+                .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?;
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password(password.as_bytes(), &salt)
+                .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
+                .to_string()
+        }
+        HashAlgorithm::Scrypt => {
+            let params = scrypt::Params::new(opts.log_n, opts.block_size, opts.parallelism, scrypt::Params::RECOMMENDED_LEN)
+                .map_err(|e| anyhow::anyhow!("Invalid scrypt parameters: {}", e))?;
+            Scrypt
+                .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+                .map_err(|e| anyhow::anyhow!("Failed to hash password: {}", e))?
+                .to_string()
+        }
+        HashAlgorithm::Bcrypt => {
+            if password.len() > BCRYPT_MAX_LEN {
+                anyhow::bail!("bcrypt only uses the first {} bytes of a password, use argon2id instead", BCRYPT_MAX_LEN);
+            }
+            bcrypt::hash(password, opts.cost)?
+        }
+    };
+    Ok(hash)
+}
+
+/// check `password` against a PHC string or bcrypt hash, the algorithm and parameters come from the hash
+pub fn process_hash_verify(password: &str, hash: &str) -> Result<bool> {
+    if ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix)) {
+        return Ok(bcrypt::verify(password, hash)?);
+    }
+    let parsed = PasswordHash::new(hash).map_err(|e| anyhow::anyhow!("Invalid PHC string: {}", e))?;
+    let verifiers: &[&dyn PasswordVerifier] = &[&Argon2::default(), &Scrypt];
+    Ok(parsed.verify_password(verifiers, password).is_ok())
+}
+
+/// read the password to hash; prompting asks twice so a typo doesn't end up in the hash
+pub fn read_new_password(path: Option<&str>) -> Result<String> {
+    let password = read_secret(path, "Password: ")?;
+    if path.is_none() && password != read_secret(None, "Confirm password: ")? {
+        anyhow::bail!("Passwords don't match");
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn opts(args: &[&str]) -> HashPasswordOpts {
+        HashPasswordOpts::parse_from(["password"].iter().chain(args))
+    }
+
+    #[test]
+    fn test_hash_and_verify() -> Result<()> {
+        // cheap parameters, the defaults are deliberately slow
+        let cases = [
+            opts(&["--algo", "argon2id", "--memory", "1024", "--iterations", "1"]),
+            opts(&["--algo", "scrypt", "--log-n", "10"]),
+            opts(&["--algo", "bcrypt", "--cost", "4"]),
+        ];
+        let prefixes = ["$argon2id$v=19$m=1024,t=1,p=1$", "$scrypt$ln=10,r=8,p=1$", "$2b$04$"];
+        for (opts, prefix) in cases.iter().zip(prefixes) {
+            let hash = process_hash_password("hunter2", opts)?;
+            assert!(hash.starts_with(prefix), "{}", hash);
+            assert!(process_hash_verify("hunter2", &hash)?);
+            assert!(!process_hash_verify("hunter3", &hash)?);
+            assert_ne!(hash, process_hash_password("hunter2", opts)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(process_hash_password("", &opts(&[])).is_err());
+        assert!(process_hash_password(&"x".repeat(73), &opts(&["--algo", "bcrypt", "--cost", "4"])).is_err());
+        assert!(process_hash_password("hunter2", &opts(&["--memory", "1"])).is_err());
+        assert!(process_hash_verify("hunter2", "not a hash").is_err());
+    }
+}
@@ -1,11 +1,42 @@
+mod csv_batch;
 mod csv_convert;
+mod csv_mask;
+mod csv_reshape;
+mod excel;
+mod fixed_width;
 mod gen_pass;
+mod derive_pass;
+mod apikey;
+mod gen_id;
+mod diceware;
+mod pattern;
+mod strength;
+mod breach;
 mod b64;
+mod codec;
 mod text;
 mod http_serve;
+mod xml;
+mod otp;
+mod hash;
 
-pub use csv_convert::process_csv;
-pub use gen_pass::process_genpass;
+pub use csv_batch::{expand_inputs, process_csv_batch};
+pub use csv_convert::{process_csv, Records};
+pub use csv_mask::ColumnMasker;
+pub use csv_reshape::{process_melt, process_pivot};
+pub use excel::SheetOpts;
+pub use fixed_width::process_fixed;
+pub use gen_pass::{genpass_entropy, genpass_rng, process_genpass, process_genpass_with_rng, PasswordPolicy};
+pub use gen_id::process_gen_id;
+pub use apikey::{apikey_hash, process_apikey_generate, process_apikey_verify};
+pub use derive_pass::process_genpass_derive;
+pub use pattern::{process_genpass_pattern, process_genpass_pronounceable};
+pub use diceware::{process_genpass_words, PassphraseOpts, Wordlist};
+pub use breach::BreachDb;
+pub use strength::{charset_entropy, process_genpass_check, strength_records, StrengthPolicy};
 pub use b64::{process_decode,process_encode};
+pub use codec::{process_codec_decode, process_codec_encode};
 pub use text::{process_text_sign, process_text_verify, process_generate};
-pub use http_serve::process_http_serve;
\ No newline at end of file
+pub use http_serve::process_http_serve;
+pub use otp::{process_otp_generate, process_otp_new, process_otp_verify, write_secret};
+pub use hash::{process_hash_password, process_hash_verify, read_new_password};
@@ -0,0 +1,263 @@
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Read, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+use crate::{
+    cli::{OtpAlgorithm, OtpParams},
+    get_raw_reader,
+};
+
+use super::{process_genpass, PasswordPolicy, StrengthPolicy};
+
+/// RFC 4648 base32 alphabet used by authenticator apps
+const BASE32_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn process_otp_generate(secret_file: &str, params: &OtpParams) -> Result<String> {
+    let key = read_key(secret_file)?;
+    Ok(hotp(&key, moving_factor(params)?, params))
+}
+
+/// accept codes up to `window` time steps around now for TOTP, or up to `window` counters ahead for HOTP
+pub fn process_otp_verify(secret_file: &str, code: &str, window: u64, params: &OtpParams) -> Result<bool> {
+    let key = read_key(secret_file)?;
+    let factor = moving_factor(params)?;
+    let first = if params.hotp { factor } else { factor.saturating_sub(window) };
+    let verified = (first..=factor.saturating_add(window))
+        .map(|counter| hotp(&key, counter, params))
+        .fold(false, |verified, expected| constant_time_eq(expected.as_bytes(), code.trim().as_bytes()) | verified);
+    Ok(verified)
+}
+
+/// create a random base32 secret of at least `bytes` bytes, returns the secret and its otpauth:// URI
+pub fn process_otp_new(issuer: &str, account: &str, bytes: Option<usize>, params: &OtpParams) -> Result<(String, String)> {
+    let bytes = bytes.unwrap_or(match params.algorithm {
+        OtpAlgorithm::Sha1 => 20,
+        OtpAlgorithm::Sha256 => 32,
+        OtpAlgorithm::Sha512 => 64,
+    });
+    if bytes < 16 {
+        anyhow::bail!("Secrets must be at least 16 bytes (128 bits), RFC 4226 recommends 20");
+    }
+    // whole 8-character base32 blocks so the secret decodes without padding
+    let policy = PasswordPolicy {
+        length: (bytes * 8).div_ceil(40) * 8,
+        charset: Some(BASE32_ALPHABET.into()),
+        min_upper: Some(0),
+        min_number: Some(0),
+        ..Default::default()
+    };
+    let secret = process_genpass(&policy, &StrengthPolicy::default())?;
+    let uri = otpauth_uri(&secret, issuer, account, params);
+    Ok((secret, uri))
+}
+
+fn otpauth_uri(secret: &str, issuer: &str, account: &str, params: &OtpParams) -> String {
+    let (kind, factor) = if params.hotp {
+        ("hotp", format!("counter={}", params.counter))
+    } else {
+        ("totp", format!("period={}", params.period))
+    };
+    format!(
+        "otpauth://{}/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&{}",
+        kind,
+        percent_encode(issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(issuer),
+        params.algorithm.to_string().to_uppercase(),
+        params.digits,
+        factor
+    )
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// read a base32 secret, ignoring case, whitespace and padding; secrets are never decompressed
+fn read_key(secret_file: &str) -> Result<Vec<u8>> {
+    let mut secret = String::new();
+    get_raw_reader(secret_file)?.read_to_string(&mut secret)?;
+    decode_secret(&secret)
+}
+
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let secret: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect::<String>()
+        .to_uppercase();
+    // authenticators accept secrets whose length isn't a multiple of 8 with non-zero trailing bits
+    let mut spec = BASE32_NOPAD.specification();
+    spec.check_trailing_bits = false;
+    let key = spec
+        .encoding()?
+        .decode(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid base32 secret: {}", e))?;
+    if key.is_empty() {
+        anyhow::bail!("Secret is empty");
+    }
+    Ok(key)
+}
+
+/// the HOTP counter, or the number of TOTP time steps since the Unix epoch
+fn moving_factor(params: &OtpParams) -> Result<u64> {
+    if params.hotp {
+        return Ok(params.counter);
+    }
+    let time = match params.time {
+        Some(time) => time,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    Ok(time / params.period)
+}
+
+/// RFC 4226 section 5.3: HMAC the counter, then dynamically truncate to `digits` decimal digits
+fn hotp(key: &[u8], counter: u64, params: &OtpParams) -> String {
+    let counter = counter.to_be_bytes();
+    let mac = match params.algorithm {
+        OtpAlgorithm::Sha1 => hmac::<Hmac<Sha1>>(key, &counter),
+        OtpAlgorithm::Sha256 => hmac::<Hmac<Sha256>>(key, &counter),
+        OtpAlgorithm::Sha512 => hmac::<Hmac<Sha512>>(key, &counter),
+    };
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let code = u32::from_be_bytes(mac[offset..offset + 4].try_into().expect("slice is 4 bytes")) & 0x7fff_ffff;
+    format!("{:0width$}", code % 10u32.pow(params.digits), width = params.digits as usize)
+}
+
+fn hmac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// compare without short-circuiting so the time taken doesn't leak how many digits matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// write a new secret to `output`, which must not exist yet; on unix the file is created
+/// readable only by the owner, so the secret is never visible with wider permissions
+pub fn write_secret(output: &str, secret: &str) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(output) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => anyhow::bail!("{} already exists, refusing to overwrite a secret", output),
+        Err(e) => return Err(e.into()),
+    };
+    writeln!(file, "{}", secret)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(algorithm: OtpAlgorithm, digits: u32) -> OtpParams {
+        OtpParams {
+            algorithm,
+            digits,
+            period: 30,
+            hotp: false,
+            counter: 0,
+            time: None,
+        }
+    }
+
+    #[test]
+    fn test_hotp_rfc4226() {
+        // RFC 4226 appendix D
+        let expected = ["755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489"];
+        let params = params(OtpAlgorithm::Sha1, 6);
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(b"12345678901234567890", counter as u64, &params), *code);
+        }
+    }
+
+    #[test]
+    fn test_totp_rfc6238() -> Result<()> {
+        // RFC 6238 appendix B, every algorithm uses the ASCII seed repeated to its own size
+        let seed = "12345678901234567890".repeat(4);
+        let cases = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (2000000000, "69279037", "90698825", "38618901"),
+        ];
+        for (time, sha1, sha256, sha512) in cases {
+            for (algorithm, len, code) in [(OtpAlgorithm::Sha1, 20, sha1), (OtpAlgorithm::Sha256, 32, sha256), (OtpAlgorithm::Sha512, 64, sha512)] {
+                let params = OtpParams {
+                    time: Some(time),
+                    ..params(algorithm, 8)
+                };
+                assert_eq!(hotp(&seed.as_bytes()[..len], moving_factor(&params)?, &params), code);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_window_and_secret() -> Result<()> {
+        // base32 of "12345678901234567890"
+        assert_eq!(decode_secret("gezd gnbv gy3t qojq gezd gnbv gy3t qojq\n")?, b"12345678901234567890");
+        assert!(decode_secret("not base32!").is_err());
+
+        let params = OtpParams {
+            time: Some(59 + 30),
+            ..params(OtpAlgorithm::Sha1, 8)
+        };
+        assert!(process_otp_verify("fixtures/otp_secret.txt", "94287082", 1, &params)?);
+        assert!(!process_otp_verify("fixtures/otp_secret.txt", "94287082", 0, &params)?);
+        assert!(!process_otp_verify("fixtures/otp_secret.txt", "9428708", 1, &params)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_otp_new() -> Result<()> {
+        let params = params(OtpAlgorithm::Sha1, 6);
+        let (secret, uri) = process_otp_new("ACME Co", "alice@example.com", None, &params)?;
+        assert_eq!(secret.len(), 32);
+        assert_eq!(decode_secret(&secret)?.len(), 20);
+        assert_eq!(
+            uri,
+            format!(
+                "otpauth://totp/ACME%20Co:alice%40example.com?secret={}&issuer=ACME%20Co&algorithm=SHA1&digits=6&period=30",
+                secret
+            )
+        );
+        assert!(process_otp_new("ACME", "alice", Some(10), &params).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_secret() -> Result<()> {
+        let output = crate::utils::test_support::TempFile::new("otp_secret.txt");
+        write_secret(output.path(), "GEZDGNBVGY3TQOJQ")?;
+        assert_eq!(read_key(output.path())?, b"1234567890");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(output.path())?.permissions().mode() & 0o777, 0o600);
+        }
+        assert!(write_secret(output.path(), "GEZDGNBVGY3TQOJQ").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::Result;
+use rand::{seq::SliceRandom, CryptoRng, Rng};
+
+use super::{
+    gen_pass::{NUMBER, SYMBOL},
+    PasswordPolicy, StrengthPolicy,
+};
+
+/// consonants and vowels for pronounceable passwords, `l` is left out like in the default sets
+const CONSONANTS: &str = "bcdfghjkmnpqrstvwxyz";
+const VOWELS: &str = "aeiou";
+
+/// one position of a pattern: a random pick from a set, or a literal character
+#[derive(Debug)]
+enum Slot {
+    Random(Vec<char>),
+    Literal(char),
+}
+
+/// generate from a template: `C`/`c` upper/lowercase consonant, `V`/`v` upper/lowercase vowel,
+/// `9` digit, `!` symbol, `\` escapes the next character and everything else is kept as is;
+/// the entropy only counts the random positions
+pub fn process_genpass_pattern(
+    pattern: &str,
+    policy: &PasswordPolicy,
+    strength: &StrengthPolicy,
+    rng: &mut (impl Rng + CryptoRng),
+) -> Result<(String, f64)> {
+    check_unused(class_options(policy), "--mode pronounceable or --pattern")?;
+    let slots = parse_pattern(pattern, policy)?;
+    policy.check_length_bounds(slots.len())?;
+    let entropy = slots
+        .iter()
+        .map(|slot| match slot {
+            Slot::Random(chars) => (chars.len() as f64).log2(),
+            Slot::Literal(_) => 0.0,
+        })
+        .sum();
+    let password = strength.generate(entropy, slots.len(), || {
+        Ok(slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Random(chars) => *chars.choose(rng).expect("sets are checked to be non-empty"),
+                Slot::Literal(c) => *c,
+            })
+            .collect())
+    })?;
+    Ok((password, entropy))
+}
+
+/// alternating lowercase consonants and vowels, `length` characters starting with a consonant
+pub fn process_genpass_pronounceable(
+    policy: &PasswordPolicy,
+    strength: &StrengthPolicy,
+    rng: &mut (impl Rng + CryptoRng),
+) -> Result<(String, f64)> {
+    if policy.length == 0 {
+        anyhow::bail!("Password length must be at least 1");
+    }
+    let pattern: String = (0..policy.length).map(|i| if i % 2 == 0 { 'c' } else { 'v' }).collect();
+    process_genpass_pattern(&pattern, policy, strength, rng)
+}
+
+/// the class options of character mode, set to a non-default value or not
+pub(super) fn class_options(policy: &PasswordPolicy) -> [(&'static str, bool); 10] {
+    [
+        ("charset", policy.charset.is_some()),
+        ("uppercase", !policy.upper),
+        ("lowercase", !policy.lower),
+        ("number", !policy.number),
+        ("symbol", !policy.symbol),
+        ("include-ambiguous", policy.include_ambiguous),
+        ("min-upper", policy.min_upper.is_some()),
+        ("min-lower", policy.min_lower.is_some()),
+        ("min-number", policy.min_number.is_some()),
+        ("min-symbol", policy.min_symbol.is_some()),
+    ]
+}
+
+/// modes that decide the characters themselves would silently ignore these options, so reject them
+pub(super) fn check_unused<'a>(options: impl IntoIterator<Item = (&'a str, bool)>, mode: &str) -> Result<()> {
+    let unused: Vec<&str> = options.into_iter().filter(|(_, set)| *set).map(|(name, _)| name).collect();
+    if !unused.is_empty() {
+        anyhow::bail!("{} can't be used with {}", unused.join(", "), mode);
+    }
+    Ok(())
+}
+
+fn parse_pattern(pattern: &str, policy: &PasswordPolicy) -> Result<Vec<Slot>> {
+    let symbols = policy.symbols.as_deref().unwrap_or(SYMBOL);
+    let mut slots = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let set = match c {
+            'C' => CONSONANTS.to_uppercase(),
+            'c' => CONSONANTS.to_string(),
+            'V' => VOWELS.to_uppercase(),
+            'v' => VOWELS.to_string(),
+            '9' => NUMBER.to_string(),
+            '!' => symbols.to_string(),
+            '\\' => {
+                let literal = chars.next().ok_or_else(|| anyhow::anyhow!("Pattern ends with an unfinished escape"))?;
+                slots.push(Slot::Literal(literal));
+                continue;
+            }
+            _ => {
+                slots.push(Slot::Literal(c));
+                continue;
+            }
+        };
+        let set: Vec<char> = set.chars().filter(|c| !policy.exclude.contains(*c)).collect();
+        if set.is_empty() {
+            anyhow::bail!("No characters left for '{}' in the pattern after exclusions", c);
+        }
+        slots.push(Slot::Random(set));
+    }
+    if !slots.iter().any(|slot| matches!(slot, Slot::Random(_))) {
+        anyhow::bail!("Pattern has no random characters");
+    }
+    Ok(slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::genpass_rng;
+
+    #[test]
+    fn test_pattern() -> Result<()> {
+        let (password, entropy) = process_genpass_pattern(
+            "Cvccvc-999-\\C!",
+            &PasswordPolicy::default(),
+            &StrengthPolicy::default(),
+            &mut genpass_rng(None),
+        )?;
+        let chars: Vec<char> = password.chars().collect();
+        assert_eq!(chars.len(), 13);
+        assert!(CONSONANTS.to_uppercase().contains(chars[0]));
+        assert!(VOWELS.contains(chars[1]));
+        assert_eq!(chars[6], '-');
+        assert!(chars[7..10].iter().all(|c| NUMBER.contains(*c)));
+        assert_eq!(&password[11..12], "C");
+        assert!(SYMBOL.contains(chars[12]));
+        // 4 consonants, 2 vowels, 3 digits and a symbol, the literals add nothing
+        let expected = 4.0 * 20f64.log2() + 2.0 * 5f64.log2() + 3.0 * 9f64.log2() + 9f64.log2();
+        assert!((entropy - expected).abs() < 1e-9);
+
+        let policy = PasswordPolicy {
+            exclude: "aeiou".into(),
+            ..Default::default()
+        };
+        let strength = StrengthPolicy::default();
+        assert!(process_genpass_pattern("cv", &policy, &strength, &mut genpass_rng(None)).is_err());
+        assert!(process_genpass_pattern("ab-", &PasswordPolicy::default(), &strength, &mut genpass_rng(None)).is_err());
+        assert!(process_genpass_pattern("c\\", &PasswordPolicy::default(), &strength, &mut genpass_rng(None)).is_err());
+
+        // class options are rejected rather than ignored
+        let policy = PasswordPolicy {
+            charset: Some("XYZ".into()),
+            min_number: Some(3),
+            ..Default::default()
+        };
+        let err = process_genpass_pattern("cvc", &policy, &strength, &mut genpass_rng(None)).unwrap_err();
+        assert_eq!(err.to_string(), "charset, min-number can't be used with --mode pronounceable or --pattern");
+        let policy = PasswordPolicy {
+            symbol: false,
+            ..Default::default()
+        };
+        assert!(process_genpass_pronounceable(&policy, &strength, &mut genpass_rng(None)).is_err());
+
+        // a policy file's length bounds apply to the generated password
+        let policy = PasswordPolicy {
+            min_length: Some(8),
+            max_length: Some(12),
+            ..Default::default()
+        };
+        assert!(process_genpass_pattern("Cvc-999", &policy, &strength, &mut genpass_rng(None)).is_err());
+        assert!(process_genpass_pattern("Cvccvc-999", &policy, &strength, &mut genpass_rng(None)).is_ok());
+        assert!(process_genpass_pronounceable(&policy, &strength, &mut genpass_rng(None)).is_err());
+        let policy = PasswordPolicy { length: 10, ..policy };
+        assert_eq!(process_genpass_pronounceable(&policy, &strength, &mut genpass_rng(None))?.0.len(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pronounceable() -> Result<()> {
+        let policy = PasswordPolicy {
+            length: 9,
+            ..Default::default()
+        };
+        let (password, entropy) = process_genpass_pronounceable(&policy, &StrengthPolicy::default(), &mut genpass_rng(None))?;
+        assert_eq!(password.len(), 9);
+        for (i, c) in password.chars().enumerate() {
+            assert!(if i % 2 == 0 { CONSONANTS } else { VOWELS }.contains(c));
+        }
+        // far less than 9 random characters from the full charset
+        assert!((entropy - (5.0 * 20f64.log2() + 4.0 * 5f64.log2())).abs() < 1e-9);
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    io::{BufRead, BufReader},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use zxcvbn::zxcvbn;
+
+use crate::{cli::StrengthOpts, get_reader};
+
+use super::{BreachDb, Records};
+
+/// maximum number of candidates generated before giving up on the policy
+const MAX_ATTEMPTS: usize = 1000;
+
+/// log10 of the guesses zxcvbn requires for each score, e.g. score 4 needs more than 10^10 guesses
+const SCORE_LOG10_GUESSES: [f64; 5] = [0.0, 3.0, 6.0, 8.0, 10.0];
+
+/// minimum strength a generated password must meet
+#[derive(Debug, Default, Clone)]
+pub struct StrengthPolicy {
+    /// minimum zxcvbn score, 0..=4
+    pub min_score: Option<u8>,
+    /// minimum entropy in bits of the generator's search space
+    pub min_entropy: Option<f64>,
+    /// reject candidates found in this breach dump
+    pub breach_db: Option<Arc<BreachDb>>,
+}
+
+impl TryFrom<&StrengthOpts> for StrengthPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(opts: &StrengthOpts) -> Result<Self> {
+        Ok(Self {
+            min_score: opts.min_score,
+            min_entropy: opts.min_entropy,
+            breach_db: opts.breach_db.as_deref().map(BreachDb::open).transpose()?.map(Arc::new),
+        })
+    }
+}
+
+impl StrengthPolicy {
+    /// regenerate candidates until one meets the policy, `entropy` is the generator's search space in bits
+    /// and `max_len` the longest candidate it can produce
+    pub fn generate(&self, entropy: f64, max_len: usize, mut gen: impl FnMut() -> Result<String>) -> Result<String> {
+        self.check_feasible(entropy, max_len)?;
+        for _ in 0..MAX_ATTEMPTS {
+            let candidate = gen()?;
+            if self.is_satisfied(&candidate) {
+                return Ok(candidate);
+            }
+        }
+        anyhow::bail!("Could not generate a password meeting the strength policy after {} attempts", MAX_ATTEMPTS)
+    }
+
+    /// fail early when the search space is too small to ever satisfy the policy
+    fn check_feasible(&self, entropy: f64, max_len: usize) -> Result<()> {
+        if let Some(min_entropy) = self.min_entropy {
+            if entropy < min_entropy {
+                anyhow::bail!(
+                    "Requested password has {:.1} bits of entropy, less than the required {:.1} bits; increase the length or enable more character classes",
+                    entropy,
+                    min_entropy
+                );
+            }
+        }
+        if let Some(min_score) = self.min_score {
+            let required = SCORE_LOG10_GUESSES
+                .get(min_score as usize)
+                .ok_or_else(|| anyhow::anyhow!("Invalid min score {}, must be 0..=4", min_score))?;
+            // zxcvbn never estimates more than 10^len guesses for a password of `len` characters
+            let max_guesses = (entropy * 2f64.log10()).min(max_len as f64);
+            if max_guesses <= *required {
+                anyhow::bail!(
+                    "Requested password can reach at most 10^{:.1} guesses, score {} needs more than 10^{}; increase the length or enable more character classes",
+                    max_guesses,
+                    min_score,
+                    required
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn is_satisfied(&self, candidate: &str) -> bool {
+        if self.breach_db.as_ref().is_some_and(|db| db.count(candidate) > 0) {
+            return false;
+        }
+        match self.min_score {
+            Some(min_score) => u8::from(zxcvbn(candidate, &[]).score()) >= min_score,
+            None => true,
+        }
+    }
+}
+
+/// entropy in bits of `length` symbols drawn uniformly from `charset_len` symbols
+pub fn charset_entropy(length: usize, charset_len: usize) -> f64 {
+    length as f64 * (charset_len as f64).log2()
+}
+
+/// one row per generated password with its zxcvbn score, offline crack time and generator entropy
+pub fn strength_records(passwords: &[(String, f64)]) -> Records {
+    let rows = passwords
+        .iter()
+        .map(|(password, entropy)| {
+            let estimate = zxcvbn(password, &[]);
+            let crack_time = estimate.crack_times().offline_slow_hashing_1e4_per_second();
+            vec![
+                password.as_str().into(),
+                u8::from(estimate.score()).into(),
+                ((entropy * 10.0).round() / 10.0).into(),
+                crack_time.to_string().into(),
+            ]
+        })
+        .collect();
+    Records {
+        headers: vec!["password".into(), "score".into(), "entropy".into(), "crack_time".into()],
+        rows,
+    }
+}
+
+/// audit existing passwords read one per line, blank lines are skipped;
+/// entries are identified by line number and the passwords are only included when `show_password` is set,
+/// with a breach dump every entry also gets the number of times it was seen in breaches
+pub fn process_genpass_check(
+    input: &str,
+    user_inputs: &[String],
+    show_password: bool,
+    breach_db: Option<&BreachDb>,
+) -> Result<Records> {
+    let user_inputs: Vec<&str> = user_inputs.iter().map(String::as_str).collect();
+    let mut headers = vec!["line".to_string()];
+    if show_password {
+        headers.push("password".into());
+    }
+    headers.extend(
+        [
+            "score",
+            "guesses_log10",
+            "warning",
+            "suggestions",
+            "online_throttling",
+            "online_no_throttling",
+            "offline_slow_hashing",
+            "offline_fast_hashing",
+        ]
+        .map(String::from),
+    );
+    if breach_db.is_some() {
+        headers.push("breach_count".into());
+    }
+
+    let mut rows = Vec::new();
+    for (i, line) in BufReader::new(get_reader(input)?).lines().enumerate() {
+        let line = line?;
+        let password = line.trim_end_matches('\r');
+        if password.is_empty() {
+            continue;
+        }
+        let estimate = zxcvbn(password, &user_inputs);
+        let feedback = estimate.feedback();
+        let crack_times = estimate.crack_times();
+
+        let mut row = vec![(i + 1).into()];
+        if show_password {
+            row.push(password.into());
+        }
+        row.extend([
+            u8::from(estimate.score()).into(),
+            ((estimate.guesses_log10() * 10.0).round() / 10.0).into(),
+            feedback.and_then(|f| f.warning()).map(|w| w.to_string()).into(),
+            feedback
+                .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect::<Vec<_>>().join(" "))
+                .unwrap_or_default()
+                .into(),
+            crack_times.online_throttling_100_per_hour().to_string().into(),
+            crack_times.online_no_throttling_10_per_second().to_string().into(),
+            crack_times.offline_slow_hashing_1e4_per_second().to_string().into(),
+            crack_times.offline_fast_hashing_1e10_per_second().to_string().into(),
+        ]);
+        if let Some(db) = breach_db {
+            row.push(db.count(password).into());
+        }
+        rows.push(row);
+    }
+    Ok(Records { headers, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infeasible_policy() {
+        let policy = StrengthPolicy {
+            min_score: None,
+            min_entropy: Some(128.0),
+            ..Default::default()
+        };
+        assert!(policy.generate(charset_entropy(8, 62), 8, || Ok("x".into())).is_err());
+
+        // 6 digits have 10^6 combinations, never enough for score 3
+        let policy = StrengthPolicy {
+            min_score: Some(3),
+            min_entropy: None,
+            ..Default::default()
+        };
+        assert!(policy.generate(charset_entropy(6, 10), 6, || Ok("x".into())).is_err());
+
+        // zxcvbn caps 8 characters at 10^8 guesses, whatever the charset
+        let policy = StrengthPolicy {
+            min_score: Some(4),
+            min_entropy: None,
+            ..Default::default()
+        };
+        assert!(policy.generate(charset_entropy(8, 67), 8, || Ok("x".into())).is_err());
+    }
+
+    #[test]
+    fn test_regenerate_until_satisfied() -> Result<()> {
+        let policy = StrengthPolicy {
+            min_score: Some(4),
+            min_entropy: None,
+            ..Default::default()
+        };
+        let mut candidates = vec!["Kx9#mQ2$vL7@pW4!", "password", "password123"];
+        let mut attempts = 0;
+        let password = policy.generate(charset_entropy(16, 70), 16, || {
+            attempts += 1;
+            Ok(candidates.pop().unwrap().into())
+        })?;
+        assert_eq!(password, "Kx9#mQ2$vL7@pW4!");
+        assert_eq!(attempts, 3);
+
+        let ret = policy.generate(charset_entropy(16, 70), 16, || Ok("password".into()));
+        assert!(ret.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_strength_records() {
+        let records = strength_records(&[("password".into(), 37.6), ("Kx9#mQ2$vL7@pW4!".into(), 98.04)]);
+        assert_eq!(records.headers, ["password", "score", "entropy", "crack_time"]);
+        assert_eq!(records.rows[0][1], 0);
+        assert_eq!(records.rows[1][1], 4);
+        assert_eq!(records.rows[1][2], 98.0);
+        assert_eq!(records.rows[0][3], "less than a second");
+    }
+
+    #[test]
+    fn test_process_genpass_check() -> Result<()> {
+        let records = process_genpass_check("fixtures/passwords.txt", &["perin".into()], false, None)?;
+        assert_eq!(records.rows.len(), 3);
+        assert!(!records.headers.contains(&"password".to_string()));
+        let serialized = serde_json::to_string(&records.rows)?;
+        assert!(!serialized.contains("Kx9#mQ2$vL7@pW4!"));
+
+        // "password" is the first line, the blank line is skipped
+        assert_eq!(records.rows[0][0], 1);
+        assert_eq!(records.rows[0][1], 0);
+        assert!(records.rows[0][3].is_string());
+        assert_eq!(records.rows[2][0], 4);
+        assert_eq!(records.rows[2][1], 4);
+
+        // the user input makes the account name guessable
+        let without_context = process_genpass_check("fixtures/passwords.txt", &[], false, None)?;
+        assert!(records.rows[1][2].as_f64() < without_context.rows[1][2].as_f64());
+
+        let records = process_genpass_check("fixtures/passwords.txt", &[], true, None)?;
+        assert_eq!(records.rows[0][1], "password");
+
+        let db = BreachDb::open("fixtures/pwned-passwords-sample.txt")?;
+        let records = process_genpass_check("fixtures/passwords.txt", &[], false, Some(&db))?;
+        assert_eq!(records.headers.last().unwrap(), "breach_count");
+        assert_eq!(records.rows[0].last().unwrap(), 10434004);
+        assert_eq!(records.rows[2].last().unwrap(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_breached() -> Result<()> {
+        let policy = StrengthPolicy {
+            breach_db: Some(Arc::new(BreachDb::open("fixtures/pwned-passwords-sample.txt")?)),
+            ..Default::default()
+        };
+        let mut candidates = vec!["Kx9#mQ2$vL7@pW4!", "hunter2", "password"];
+        let password = policy.generate(charset_entropy(16, 70), 16, || Ok(candidates.pop().unwrap().into()))?;
+        assert_eq!(password, "Kx9#mQ2$vL7@pW4!");
+        Ok(())
+    }
+}
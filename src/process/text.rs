@@ -4,9 +4,9 @@ use rand::rngs::OsRng;
 use std::{io::Read, path::Path};
 use std::fs;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
-use crate::{get_reader, TextSignFormat};
+use crate::{get_raw_reader, get_reader, TextSignFormat};
 
-use super::process_genpass;
+use super::{process_genpass, PasswordPolicy, StrengthPolicy};
 
 pub trait TextSign {
     // sign the data from the reader and return signature
@@ -39,8 +39,9 @@ struct Ed25519Verifier {
     key: VerifyingKey,
 }
 
-pub fn process_text_sign(input: &str, key: &str, format:TextSignFormat) -> Result<String> {
-    let mut reader = get_reader(input)?;
+/// compressed input is signed after decompression unless `raw` is set
+pub fn process_text_sign(input: &str, key: &str, format:TextSignFormat, raw: bool) -> Result<String> {
+    let mut reader = if raw { get_raw_reader(input)? } else { get_reader(input)? };
 
     let signed = match format {
         TextSignFormat::Blake3 => {
@@ -59,8 +60,8 @@ pub fn process_text_sign(input: &str, key: &str, format:TextSignFormat) -> Resul
         Ok(signed)
     }
 
-pub fn process_text_verify(input: &str, key: &str, format:TextSignFormat, sig:&str) -> Result<bool > {
-        let mut reader = get_reader(input)?;
+pub fn process_text_verify(input: &str, key: &str, format:TextSignFormat, sig:&str, raw: bool) -> Result<bool > {
+        let mut reader = if raw { get_raw_reader(input)? } else { get_reader(input)? };
         let sig = URL_SAFE_NO_PAD.decode(sig)?;
         let verified = match format {
             TextSignFormat::Blake3 => {
@@ -87,7 +88,7 @@ impl TextSign for Blake3 {
     fn sign(&self, reader: &mut dyn Read) -> Result<Vec<u8>> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        Ok(blake3::keyed_hash(&self.key,&buf).as_bytes().to_vec())
+        Ok(self.keyed_hash(&buf).as_bytes().to_vec())
     }
 }
 
@@ -106,7 +107,7 @@ impl TextVerify for Blake3 {
     fn verify(&self, mut reader: impl Read, sig: &[u8]) -> Result<bool> {
         let mut buf = Vec::new();
         reader.read_to_end(&mut buf)?;
-        let hash = blake3::keyed_hash(&self.key,&buf);
+        let hash = self.keyed_hash(&buf);
         let hash = hash.as_bytes();
         Ok(hash == sig)
     }
@@ -145,7 +146,7 @@ impl KeyLoader for Ed25519Verifier {
 
 impl KeyGenerator for Blake3 {
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let key = process_genpass(32, true, true, true, true)?;
+        let key = process_genpass(&PasswordPolicy { length: 32, ..Default::default() }, &StrengthPolicy::default())?;
         let key = key.as_bytes().to_vec();
         Ok(vec![key])
     }
@@ -174,6 +175,17 @@ impl Blake3 {
         Ok(signer)
     }
 
+    /// keyed BLAKE3 hash of `data` under this key
+    pub fn keyed_hash(&self, data: &[u8]) -> blake3::Hash {
+        blake3::keyed_hash(&self.key, data)
+    }
+
+    /// an independent subkey for `context`, so one key file can serve several features
+    /// without their outputs being linkable
+    pub fn derive(&self, context: &str) -> Self {
+        Self::new(blake3::derive_key(context, &self.key))
+    }
+
 }
 
 impl Ed25519Signer {
@@ -230,4 +242,20 @@ mod test {
         assert!(pk.verify(&data[..], &sig)?);
         Ok(())
     } 
+
+    #[test]
+    fn test_sign_compressed_input() -> Result<()> {
+        let plain = crate::utils::test_support::TempFile::with_data("text_sign.txt", b"hello world")?;
+        let compressed = crate::utils::test_support::TempFile::new("text_sign.txt.gz");
+        let mut writer = crate::get_writer(compressed.path())?;
+        std::io::Write::write_all(&mut writer, b"hello world")?;
+        writer.finish()?;
+
+        let sig = process_text_sign(plain.path(), "fixtures/blake3.txt", TextSignFormat::Blake3, false)?;
+        assert_eq!(process_text_sign(compressed.path(), "fixtures/blake3.txt", TextSignFormat::Blake3, false)?, sig);
+        assert_ne!(process_text_sign(compressed.path(), "fixtures/blake3.txt", TextSignFormat::Blake3, true)?, sig);
+        assert!(process_text_verify(compressed.path(), "fixtures/blake3.txt", TextSignFormat::Blake3, &sig, false)?);
+        assert!(!process_text_verify(compressed.path(), "fixtures/blake3.txt", TextSignFormat::Blake3, &sig, true)?);
+        Ok(())
+    }
 }
\ No newline at end of file
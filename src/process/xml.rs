@@ -0,0 +1,181 @@
+use anyhow::Result;
+use quick_xml::{
+    events::{BytesDecl, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+use serde_json::Value;
+use std::io::BufReader;
+
+use crate::{cli::XmlOpts, get_reader};
+
+use super::{csv_convert::value_to_field, Records};
+
+const XML_EXTENSIONS: &[&str] = &[".xml", ".xml.gz", ".xml.zst", ".xml.bz2"];
+
+/// 将记录序列化为 XML，字段作为子元素或行元素的属性
+pub(crate) fn to_xml(records: &Records, opts: &XmlOpts) -> Result<String> {
+    let names: Vec<String> = records.headers.iter().map(|h| element_name(h)).collect();
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer.write_event(Event::Start(BytesStart::new(&opts.xml_root)))?;
+    for row in &records.rows {
+        // null 字段作为属性时省略，作为子元素时输出为空元素
+        if opts.xml_attributes {
+            let mut elem = BytesStart::new(&opts.xml_row);
+            for (name, value) in names.iter().zip(row) {
+                if !value.is_null() {
+                    elem.push_attribute((name.as_str(), value_to_field(value).as_str()));
+                }
+            }
+            writer.write_event(Event::Empty(elem))?;
+        } else {
+            writer.create_element(&opts.xml_row).write_inner_content(|w| {
+                for (name, value) in names.iter().zip(row) {
+                    let elem = w.create_element(name);
+                    match value {
+                        Value::Null => elem.write_empty()?,
+                        v => elem.write_text_content(BytesText::new(&value_to_field(v)))?,
+                    };
+                }
+                Ok::<_, std::io::Error>(())
+            })?;
+        }
+    }
+    writer.write_event(Event::End(BytesStart::new(&opts.xml_root).to_end()))?;
+    let mut content = String::from_utf8(writer.into_inner())?;
+    content.push('\n');
+    Ok(content)
+}
+
+/// 根据扩展名判断输入是否为 XML 文件（包括压缩的 .xml.gz 等）
+pub(crate) fn is_xml(input: &str) -> bool {
+    XML_EXTENSIONS.iter().any(|ext| input.ends_with(ext))
+}
+
+/// 读取 XML 文档：根元素的每个子元素为一行，其属性和子元素的文本为字段
+pub(crate) fn read_xml(input: &str) -> Result<Records> {
+    let mut reader = Reader::from_reader(BufReader::new(get_reader(input)?));
+    reader.config_mut().trim_text(true);
+
+    let mut records = Records::default();
+    let mut row: Vec<(String, String)> = Vec::new();
+    let mut field: Option<(String, String)> = None;
+    let mut depth = 0;
+    let mut buf = Vec::new();
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) => {
+                depth += 1;
+                let name = String::from_utf8(e.name().as_ref().to_vec())?;
+                match depth {
+                    2 => {
+                        for attr in e.attributes() {
+                            let attr = attr?;
+                            let key = String::from_utf8(attr.key.as_ref().to_vec())?;
+                            row.push((key, attr.unescape_value()?.into_owned()));
+                        }
+                    }
+                    3 => field = Some((name, String::new())),
+                    _ => {}
+                }
+            }
+            Event::Text(e) => {
+                if let Some((_, text)) = field.as_mut() {
+                    text.push_str(&e.unescape()?);
+                }
+            }
+            Event::CData(e) => {
+                if let Some((_, text)) = field.as_mut() {
+                    text.push_str(std::str::from_utf8(e)?);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        // 自闭合元素没有对应的 End 事件，需要同时处理
+        if matches!(event, Event::End(_) | Event::Empty(_)) {
+            match depth {
+                2 => records.push_fields(std::mem::take(&mut row)),
+                3 => row.extend(field.take()),
+                _ => {}
+            }
+            depth -= 1;
+        }
+        buf.clear();
+    }
+    Ok(records)
+}
+
+impl Records {
+    /// 按字段名追加一行，新出现的字段追加为新列，缺失的字段为 null
+    fn push_fields(&mut self, fields: Vec<(String, String)>) {
+        let mut row = vec![Value::Null; self.headers.len()];
+        for (name, value) in fields {
+            let pos = match self.headers.iter().position(|h| *h == name) {
+                Some(pos) => pos,
+                None => {
+                    self.headers.push(name);
+                    for r in self.rows.iter_mut() {
+                        r.push(Value::Null);
+                    }
+                    row.push(Value::Null);
+                    self.headers.len() - 1
+                }
+            };
+            row[pos] = Value::String(value);
+        }
+        self.rows.push(row);
+    }
+}
+
+/// 将列名转换为合法的 XML 元素名，例如 "Kit Number" -> "Kit_Number"
+fn element_name(header: &str) -> String {
+    let mut name: String = header
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Records {
+        Records {
+            headers: vec!["Name".into(), "Kit Number".into()],
+            rows: vec![vec!["Paulo Dybala".into(), 10.into()], vec!["Tom & Jerry".into(), Value::Null]],
+        }
+    }
+
+    #[test]
+    fn test_to_xml() -> Result<()> {
+        let xml = to_xml(&records(), &XmlOpts::default())?;
+        assert!(xml.contains("<record>\n    <Name>Paulo Dybala</Name>\n    <Kit_Number>10</Kit_Number>\n  </record>"));
+        assert!(xml.contains("<Name>Tom &amp; Jerry</Name>"));
+
+        let opts = XmlOpts {
+            xml_root: "players".into(),
+            xml_row: "player".into(),
+            xml_attributes: true,
+        };
+        let xml = to_xml(&records(), &opts)?;
+        assert!(xml.contains("<players>\n  <player Name=\"Paulo Dybala\" Kit_Number=\"10\"/>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_xml() -> Result<()> {
+        let records = read_xml("fixtures/players.xml")?;
+        assert_eq!(records.headers, ["id", "Name", "Position", "Kit_Number"]);
+        assert_eq!(records.rows.len(), 3);
+        assert_eq!(records.rows[0][1], Value::from("Wojciech Szczesny"));
+        assert_eq!(records.rows[1][3], Value::Null);
+        assert_eq!(records.rows[2][2], Value::from("Forward & Captain"));
+        Ok(())
+    }
+}
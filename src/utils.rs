@@ -1,11 +1,273 @@
 use anyhow::Result;
-use std::{fs::File, io::Read};
+use bzip2::{read::MultiBzDecoder, write::BzEncoder};
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    path::Path,
+};
 
+/// gzip ID1, ID2 and CM (deflate, the only method in use)
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b, 0x08];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+/// what follows `BZh` and the block size digit: pi for a block, sqrt(pi) for an empty stream
+const BZIP2_BLOCK_MAGIC: &[u8] = &[0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+const BZIP2_EOS_MAGIC: &[u8] = &[0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+
+/// open `input` ("-" for stdin), transparently decompressing gzip/zstd/bzip2 data
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
+    decompress(get_raw_reader(input)?)
+}
+
+/// open `input` ("-" for stdin) as is, for consumers that must see the exact bytes
+pub fn get_raw_reader(input: &str) -> Result<Box<dyn Read>> {
     let  reader: Box<dyn Read> = if input == "-"{
         Box::new(std::io::stdin())
     } else {
         Box::new(File::open(input)?)
     };
     Ok(reader)
-}
\ No newline at end of file
+}
+
+/// create `output` ("-" for stdout), compressing according to its extension (.gz, .zst, .bz2);
+/// call `finish` when done, dropping the writer can lose the end of the data or its errors
+pub fn get_writer(output: &str) -> Result<OutputWriter> {
+    if output == "-" {
+        return Ok(OutputWriter::Plain(Box::new(std::io::stdout())));
+    }
+    let file = File::create(output)?;
+    let ext = Path::new(output).extension().and_then(|ext| ext.to_str());
+    let writer = match ext {
+        Some("gz") => OutputWriter::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+        Some("zst") => OutputWriter::Zstd(zstd::Encoder::new(file, 0)?),
+        Some("bz2") => OutputWriter::Bzip2(BzEncoder::new(file, bzip2::Compression::default())),
+        _ => OutputWriter::Plain(Box::new(file)),
+    };
+    Ok(writer)
+}
+
+/// writer returned by `get_writer`
+pub enum OutputWriter {
+    Plain(Box<dyn Write>),
+    Gzip(GzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+    Bzip2(BzEncoder<File>),
+}
+
+impl OutputWriter {
+    /// write the compressed stream's trailer and flush, reporting any error on the way
+    pub fn finish(self) -> Result<()> {
+        let mut file = match self {
+            OutputWriter::Plain(mut writer) => return Ok(writer.flush()?),
+            OutputWriter::Gzip(encoder) => encoder.finish()?,
+            OutputWriter::Zstd(encoder) => encoder.finish()?,
+            OutputWriter::Bzip2(encoder) => encoder.finish()?,
+        };
+        file.flush()?;
+        Ok(())
+    }
+
+    fn inner(&mut self) -> &mut dyn Write {
+        match self {
+            OutputWriter::Plain(writer) => writer,
+            OutputWriter::Gzip(encoder) => encoder,
+            OutputWriter::Zstd(encoder) => encoder,
+            OutputWriter::Bzip2(encoder) => encoder,
+        }
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner().flush()
+    }
+}
+
+/// run `write` against `output` ("-" for stdout) and keep its bytes exactly as they are, without compressing;
+/// only a terminal is refused data that isn't UTF-8, since it would print garbage
+pub fn write_binary(output: &str, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    if output != "-" {
+        let mut writer = BufWriter::new(File::create(output)?);
+        write(&mut writer)?;
+        writer.flush()?;
+        return Ok(());
+    }
+    let stdout = std::io::stdout().lock();
+    if stdout.is_terminal() {
+        let mut buf = Vec::new();
+        write(&mut buf)?;
+        let text = String::from_utf8(buf)
+            .map_err(|_| anyhow::anyhow!("Output is binary data, write it to a file with -o/--output or redirect stdout"))?;
+        println!("{}", text);
+    } else {
+        let mut writer = BufWriter::new(stdout);
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// read a secret from `path` ("-" for stdin), or prompt for it on the terminal without echo;
+/// the trailing newline is dropped
+pub fn read_secret(path: Option<&str>, prompt: &str) -> Result<String> {
+    let secret = match path {
+        Some(path) => {
+            let mut secret = String::new();
+            // secrets are read byte for byte, never decompressed
+            get_raw_reader(path)?.read_to_string(&mut secret)?;
+            secret
+        }
+        None => rpassword::prompt_password(prompt)?,
+    };
+    Ok(secret.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn decompress(reader: Box<dyn Read>) -> Result<Box<dyn Read>> {
+    // peek at the magic bytes without consuming them
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    let reader: Box<dyn Read> = if magic.starts_with(GZIP_MAGIC) {
+        Box::new(MultiGzDecoder::new(reader))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Box::new(zstd::Decoder::with_buffer(reader)?)
+    } else if is_bzip2(magic) {
+        Box::new(MultiBzDecoder::new(reader))
+    } else {
+        Box::new(reader)
+    };
+    Ok(reader)
+}
+
+fn is_bzip2(magic: &[u8]) -> bool {
+    magic.len() >= 10
+        && magic.starts_with(BZIP2_MAGIC)
+        && (b'1'..=b'9').contains(&magic[3])
+        && (magic[4..10] == *BZIP2_BLOCK_MAGIC || magic[4..10] == *BZIP2_EOS_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_support::TempFile, *};
+
+    fn read_all(input: &str) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        get_reader(input)?.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn test_get_reader_decompress() -> Result<()> {
+        let plain = read_all("fixtures/test1.txt")?;
+        for ext in ["gz", "zst", "bz2"] {
+            assert_eq!(read_all(&format!("fixtures/test1.txt.{}", ext))?, plain);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_input_with_magic_prefix() -> Result<()> {
+        // look like compressed data for the first bytes only
+        for data in [&b"BZh is plain text"[..], b"BZh9 is plain text too", &[0x1f, 0x8b, 0x00, 0x42]] {
+            let input = TempFile::with_data("magic_prefix.txt", data)?;
+            assert_eq!(read_all(input.path())?, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_secret_is_raw() -> Result<()> {
+        let input = TempFile::with_data("read_secret.txt", b"BZh91AY&SY secret\r\n")?;
+        assert_eq!(read_secret(Some(input.path()), "")?, "BZh91AY&SY secret");
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_writer_compress() -> Result<()> {
+        for (ext, magic) in [("zst", ZSTD_MAGIC), ("gz", GZIP_MAGIC), ("bz2", BZIP2_MAGIC)] {
+            let output = TempFile::new(&format!("get_writer.txt.{}", ext));
+            let mut writer = get_writer(output.path())?;
+            writer.write_all(b"hello world")?;
+            writer.finish()?;
+
+            assert!(std::fs::read(output.path())?.starts_with(magic), "{}", ext);
+            assert_eq!(read_all(output.path())?, b"hello world");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_binary() -> Result<()> {
+        // not valid UTF-8, and gzip magic that must not be decompressed or recompressed
+        let data = [0x1f, 0x8b, 0xff, 0x00, b'\n'];
+        let output = TempFile::new("write_binary.gz");
+        write_binary(output.path(), |writer| Ok(writer.write_all(&data)?))?;
+        assert_eq!(std::fs::read(output.path())?, data);
+        Ok(())
+    }
+}
+
+/// helpers shared by the tests of every module
+#[cfg(test)]
+pub(crate) mod test_support {
+    use anyhow::Result;
+    use std::{
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// a path in the temp dir that is unique to this process and call, so tests running in parallel never share one
+    fn unique_path(name: &str) -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("rcli_{}_{}_{}", std::process::id(), n, name))
+    }
+
+    /// a uniquely named temp file, removed on drop
+    pub(crate) struct TempFile(PathBuf);
+
+    impl TempFile {
+        pub(crate) fn new(name: &str) -> Self {
+            Self(unique_path(name))
+        }
+
+        pub(crate) fn with_data(name: &str, data: &[u8]) -> Result<Self> {
+            let file = Self::new(name);
+            std::fs::write(&file.0, data)?;
+            Ok(file)
+        }
+
+        pub(crate) fn path(&self) -> &str {
+            self.0.to_str().expect("temp paths are UTF-8")
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// a uniquely named temp directory, not created up front and removed with its contents on drop
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new(name: &str) -> Self {
+            Self(unique_path(name))
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+}